@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+
+use quoridor::{AIControlledBoard, Board, Move, PreCalc};
+
+use crate::grid::winner;
+
+/// How many visits every position of the game gets when it's analyzed.
+pub const ANALYSIS_VISITS: u32 = 100_000;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum MoveLabel {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveLabel {
+    /// The label of a move that is not the move of the engine, by how much it lowered the win rate of the player.
+    pub fn from_loss(loss: f32) -> MoveLabel {
+        match loss {
+            loss if loss < 0.02 => MoveLabel::Best,
+            loss if loss < 0.05 => MoveLabel::Good,
+            loss if loss < 0.10 => MoveLabel::Inaccuracy,
+            loss if loss < 0.20 => MoveLabel::Mistake,
+            _ => MoveLabel::Blunder,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MoveLabel::Best => "best",
+            MoveLabel::Good => "good",
+            MoveLabel::Inaccuracy => "inaccuracy",
+            MoveLabel::Mistake => "mistake",
+            MoveLabel::Blunder => "blunder",
+        }
+    }
+}
+
+/// One move of an analyzed game. The win rates are for the player who made the move.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct MoveAnalysis {
+    pub game_move: Move,
+    pub notation: String,
+    pub player: usize,
+    pub win_rate_before: f32,
+    pub win_rate_after: f32,
+    pub label: MoveLabel,
+    /// The move the engine preferred, if that is another move.
+    pub best_move: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct GameAnalysis {
+    /// The encoding of the position the moves were played from. Analyses saved before it was kept have none.
+    #[serde(default)]
+    pub start: String,
+    pub visits: u32,
+    pub moves: Vec<MoveAnalysis>,
+    /// Per player, from 0 to 100.
+    pub accuracy: [f32; 2],
+}
+
+impl GameAnalysis {
+    /// The chance white wins from every position of the game, starting with the position before the first move.
+    pub fn win_rates_zero(&self) -> Vec<f32> {
+        let mut win_rates = vec![];
+        for analysis in &self.moves {
+            win_rates.push(match analysis.player {
+                0 => analysis.win_rate_before,
+                _ => 1.0 - analysis.win_rate_before,
+            });
+        }
+        if let Some(last) = self.moves.last() {
+            win_rates.push(match last.player {
+                0 => last.win_rate_after,
+                _ => 1.0 - last.win_rate_after,
+            });
+        }
+        win_rates
+    }
+}
+
+// How well a single move was played from 0 to 100, the formula lichess uses for chess.
+fn move_accuracy(loss: f32) -> f32 {
+    (103.1668 * (-0.04354 * loss * 100.0).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// Replays a game and searches every position of it, a batch at a time so it can run next to the rest of the
+/// engine.
+pub struct GameAnalyzer {
+    start: Board,
+    moves: Vec<Move>,
+    visits: u32,
+    ai_controlled_board: AIControlledBoard,
+    // Per position searched so far, the win rate of the player to move and the move the engine prefers.
+    evaluations: Vec<(f32, Option<Move>)>,
+}
+
+impl GameAnalyzer {
+    /// Analyzes the game with `moves` played from `start`, which doesn't have to be the start of the game.
+    pub fn new(start: Board, moves: Vec<Move>, visits: u32) -> GameAnalyzer {
+        GameAnalyzer {
+            ai_controlled_board: AIControlledBoard::decode(&start.encode()).unwrap(),
+            start,
+            moves,
+            visits,
+            evaluations: vec![],
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.evaluations.len() > self.moves.len()
+    }
+
+    /// The share of the positions that are searched.
+    pub fn progress(&self) -> f32 {
+        self.evaluations.len() as f32 / (self.moves.len() + 1) as f32
+    }
+
+    /// Searches the current position for `number_of_steps`, and moves on to the next position once it has enough
+    /// visits.
+    pub fn step(&mut self, number_of_steps: usize, pre_calc: &PreCalc) {
+        if self.is_done() {
+            return;
+        }
+        let board = &self.ai_controlled_board.board;
+        // After the last move the player to move has lost, there is nothing to search.
+        let evaluation = if winner(board).is_some() {
+            Some((0.0, None))
+        } else {
+            let resp = self.ai_controlled_board.ai_move(number_of_steps, pre_calc);
+            let mc_node = &self.ai_controlled_board.relevant_mc_tree.mc_node;
+            if mc_node.number_visits() >= self.visits || self.ai_controlled_board.is_played_out() {
+                let score = mc_node.scores();
+                // The scores are from the perspective of the player who made the last move.
+                let win_rate = 1.0 - score.0 as f32 / score.1.max(1) as f32;
+                Some((win_rate, Some(resp.suggested_move)))
+            } else {
+                None
+            }
+        };
+        if let Some(evaluation) = evaluation {
+            self.evaluations.push(evaluation);
+            if let Some(game_move) = self.moves.get(self.evaluations.len() - 1) {
+                self.ai_controlled_board.game_move(*game_move);
+            }
+        }
+    }
+
+    pub fn finish(self) -> GameAnalysis {
+        let mut board = self.start.clone();
+        let mut moves = vec![];
+        let mut losses = [vec![], vec![]];
+        for (index, game_move) in self.moves.iter().enumerate() {
+            let player = board.turn % 2;
+            let (win_rate_before, best_move) = self.evaluations[index];
+            let win_rate_after = 1.0 - self.evaluations[index + 1].0;
+            let loss = (win_rate_before - win_rate_after).max(0.0);
+            losses[player].push(loss);
+            let label = if best_move == Some(*game_move) {
+                MoveLabel::Best
+            } else {
+                MoveLabel::from_loss(loss)
+            };
+            moves.push(MoveAnalysis {
+                game_move: *game_move,
+                notation: game_move.to_quoridor_strat_notation(&board),
+                player,
+                win_rate_before,
+                win_rate_after,
+                label,
+                best_move: best_move
+                    .filter(|best_move| best_move != game_move)
+                    .map(|best_move| best_move.to_quoridor_strat_notation(&board)),
+            });
+            board.game_move(*game_move);
+        }
+        let accuracy = losses.map(|losses| {
+            if losses.is_empty() {
+                100.0
+            } else {
+                losses.iter().map(|loss| move_accuracy(*loss)).sum::<f32>() / losses.len() as f32
+            }
+        });
+        GameAnalysis {
+            start: self.start.encode(),
+            visits: self.visits,
+            moves,
+            accuracy,
+        }
+    }
+}
+
+/// Analyzes the whole game at once, for when there is nothing else to do in the meantime.
+pub fn analyze_game(
+    start: Board,
+    moves: Vec<Move>,
+    visits: u32,
+    pre_calc: &PreCalc,
+) -> GameAnalysis {
+    let mut analyzer = GameAnalyzer::new(start, moves, visits);
+    while !analyzer.is_done() {
+        analyzer.step(10_000, pre_calc);
+    }
+    analyzer.finish()
+}
+
+/// A game as it's stored between visits, with its analysis once that is done.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct SavedGame {
+    pub moves: Vec<Move>,
+    pub analysis: Option<GameAnalysis>,
+}
+
+impl SavedGame {
+    /// The analysis of the game with `moves` from the position with encoding `start`, if it was done before with the
+    /// same number of visits.
+    pub fn cached_analysis(
+        &self,
+        start: &str,
+        moves: &[Move],
+        visits: u32,
+    ) -> Option<&GameAnalysis> {
+        self.analysis.as_ref().filter(|analysis| {
+            analysis.start == start && self.moves == moves && analysis.visits == visits
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_board::parse_move;
+
+    // An analyzer that already searched every position, with these win rates of the player to move and the moves the
+    // engine prefers.
+    fn searched(
+        start: &str,
+        notations: &[&str],
+        evaluations: &[(f32, Option<&str>)],
+    ) -> GameAnalyzer {
+        let start = Board::decode(start).unwrap();
+        let mut board = start.clone();
+        let mut moves = vec![];
+        let mut parsed_evaluations = vec![];
+        for (index, (win_rate, best_move)) in evaluations.iter().enumerate() {
+            parsed_evaluations.push((
+                *win_rate,
+                best_move.map(|best_move| parse_move(&board, best_move).unwrap()),
+            ));
+            if let Some(notation) = notations.get(index) {
+                let game_move = parse_move(&board, notation).unwrap();
+                moves.push(game_move);
+                board.game_move(game_move);
+            }
+        }
+        GameAnalyzer {
+            ai_controlled_board: AIControlledBoard::decode(&board.encode()).unwrap(),
+            start,
+            moves,
+            visits: 1,
+            evaluations: parsed_evaluations,
+        }
+    }
+
+    #[test]
+    fn moves_are_labeled_by_the_win_rate_they_lose() {
+        assert_eq!(MoveLabel::from_loss(0.0), MoveLabel::Best);
+        assert_eq!(MoveLabel::from_loss(0.03), MoveLabel::Good);
+        assert_eq!(MoveLabel::from_loss(0.07), MoveLabel::Inaccuracy);
+        assert_eq!(MoveLabel::from_loss(0.15), MoveLabel::Mistake);
+        assert_eq!(MoveLabel::from_loss(0.3), MoveLabel::Blunder);
+    }
+
+    #[test]
+    fn a_move_that_loses_nothing_is_fully_accurate() {
+        assert!(move_accuracy(0.0) > 99.99);
+        assert!(move_accuracy(0.1) < move_accuracy(0.05));
+        assert_eq!(move_accuracy(1.0), 0.0);
+    }
+
+    #[test]
+    fn a_move_that_gives_the_game_away_is_a_blunder() {
+        let analysis = searched(
+            "0;10E1;10E9",
+            &["e2", "e8"],
+            &[(0.5, Some("e2")), (0.5, Some("d9")), (0.8, None)],
+        )
+        .finish();
+
+        assert_eq!(analysis.start, "0;10E1;10E9");
+        let [white, black] = &analysis.moves[..] else {
+            panic!("expected two moves");
+        };
+        assert_eq!(
+            (white.player, white.label, white.best_move.clone()),
+            (0, MoveLabel::Best, None)
+        );
+        assert_eq!(black.player, 1);
+        assert_eq!(black.label, MoveLabel::Blunder);
+        assert_eq!(black.best_move.as_deref(), Some("d9"));
+        assert!((black.win_rate_after - 0.2).abs() < 1e-6);
+        assert!(analysis.accuracy[0] > analysis.accuracy[1]);
+        let win_rates = analysis.win_rates_zero();
+        assert_eq!(win_rates.len(), 3);
+        assert!((win_rates[2] - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn a_game_from_a_linked_position_is_analyzed_from_there() {
+        // Black is to move in the linked position.
+        let analysis =
+            searched("5;9E3;10E7;D6h", &["f7"], &[(0.4, Some("f7")), (0.6, None)]).finish();
+
+        assert_eq!(analysis.start, "5;9E3;10E7;D6h");
+        assert_eq!(analysis.moves[0].player, 1);
+        assert_eq!(analysis.moves[0].notation, "f7");
+        assert_eq!(analysis.moves[0].label, MoveLabel::Best);
+        for win_rate in analysis.win_rates_zero() {
+            assert!((win_rate - 0.6).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn the_cached_analysis_is_only_used_for_the_same_game() {
+        let analysis = searched("0;10E1;10E9", &["e2"], &[(0.5, Some("e2")), (0.5, None)]).finish();
+        let saved = SavedGame {
+            moves: analysis
+                .moves
+                .iter()
+                .map(|analysis| analysis.game_move)
+                .collect(),
+            analysis: Some(analysis.clone()),
+        };
+
+        assert!(saved
+            .cached_analysis("0;10E1;10E9", &saved.moves, 1)
+            .is_some());
+        assert!(saved
+            .cached_analysis("5;9E3;10E7;D6h", &saved.moves, 1)
+            .is_none());
+        assert!(saved
+            .cached_analysis("0;10E1;10E9", &saved.moves, 2)
+            .is_none());
+        assert!(saved.cached_analysis("0;10E1;10E9", &[], 1).is_none());
+    }
+}
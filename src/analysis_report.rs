@@ -0,0 +1,81 @@
+use dioxus::prelude::*;
+
+use quoridor_frontend::analysis::{GameAnalysis, MoveLabel};
+use quoridor_frontend::i18n::Locale;
+
+use crate::settings::use_locale;
+
+fn label_color(label: MoveLabel) -> &'static str {
+    match label {
+        MoveLabel::Best => "bg-green-500",
+        MoveLabel::Good => "bg-blue-500",
+        MoveLabel::Inaccuracy => "bg-amber-400",
+        MoveLabel::Mistake => "bg-amber-700",
+        MoveLabel::Blunder => "bg-red-500",
+    }
+}
+
+fn label_text(locale: Locale, label: MoveLabel) -> &'static str {
+    locale.text(match label {
+        MoveLabel::Best => "label_best",
+        MoveLabel::Good => "label_good",
+        MoveLabel::Inaccuracy => "label_inaccuracy",
+        MoveLabel::Mistake => "label_mistake",
+        MoveLabel::Blunder => "label_blunder",
+    })
+}
+
+#[derive(Props, PartialEq)]
+pub struct AnalysisReportProps {
+    analysis: GameAnalysis,
+}
+
+// Every move of the analyzed game with how good it was, and the move the engine would have played instead.
+pub fn AnalysisReport(cx: Scope<AnalysisReportProps>) -> Element {
+    let analysis = &cx.props.analysis;
+    let locale = use_locale(cx);
+    // The accuracy is out of 100, the win rates are fractions.
+    let accuracy = |player: usize| {
+        locale.format(
+            "analysis_accuracy",
+            &[
+                ("player", locale.player(player)),
+                ("accuracy", &locale.percent(analysis.accuracy[player] / 100.0, 0)),
+            ],
+        )
+    };
+    let white_accuracy = accuracy(0);
+    let black_accuracy = accuracy(1);
+
+    cx.render(rsx! {
+        div { class: "w-full p-4 flex flex-col items-center",
+            div { class: "text-2xl font-semibold", locale.text("analysis") },
+            div { class: "flex space-x-4 p-2",
+                div { class: "text-xl font-bold", "{white_accuracy}" },
+                div { class: "text-xl font-bold", "{black_accuracy}" },
+            },
+            div { class: "w-full max-h-[300px] overflow-auto p-2",
+                analysis.moves.iter().enumerate().map(|(index, move_analysis)| {
+                    let color = label_color(move_analysis.label);
+                    let before = locale.percent(move_analysis.win_rate_before, 0);
+                    let after = locale.percent(move_analysis.win_rate_after, 0);
+                    let player = locale.player(move_analysis.player);
+                    let label = label_text(locale, move_analysis.label);
+                    rsx! {
+                        div { key: "{index}", class: "flex items-center space-x-2",
+                            span { class: "w-12", "{index + 1}." },
+                            span { class: "w-16", "{player}" },
+                            span { class: "font-bold w-12", "{move_analysis.notation}" },
+                            span { class: "{color} text-white rounded px-1", "{label}" },
+                            span { "{before} → {after}" },
+                            if let Some(best_move) = &move_analysis.best_move {
+                                let engine_move = locale.format("analysis_engine_move", &[("move", best_move)]);
+                                rsx! { span { class: "text-gray-600", "{engine_move}" } }
+                            }
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,298 @@
+// Builds the opening book: runs a deep search on every position and writes its tree to `precalc/<encoding>.mc_node`
+// and the chance white wins from it to `to_precalc.json`, the layout the site serves from `docs/precalc`. Positions
+// come from the command line, from `--positions` with one encoding per line, or with `--depth` from expanding the
+// most visited moves of every searched position.
+//
+//     cargo run --release --bin quoridor-book -- --depth 2 --visits 20000000
+//
+// Every finished position is written straight away and a search in progress is saved every `CHECKPOINT_VISITS` to
+// `partial/`, running the same command again continues where an interrupted build stopped. A `book.qbook` in the
+// folder is removed with the first new tree, so the old trees in it don't shadow the new ones, and packed again at
+// the end of the run.
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use quoridor::{AIControlledBoard, Board, MonteCarloTree, PreCalc};
+use quoridor_frontend::book_manifest::{BookManifest, TreeEntry, MANIFEST_FILE};
+use quoridor_frontend::engine::{book_position, BookError, EngineConfig};
+use quoridor_frontend::grid::winner;
+use quoridor_frontend::native::{pack_directory, read_manifest};
+use quoridor_frontend::packed_book::PACKED_BOOK_FILE;
+
+const START_POSITION: &str = "0;10E1;10E9";
+const STEPS_PER_BATCH: usize = 100_000;
+const CHECKPOINT_VISITS: u32 = 2_000_000;
+
+struct Options {
+    out: PathBuf,
+    positions: Vec<String>,
+    depth: usize,
+    visits: u32,
+    // With `--depth`, moves with less than this share of the visits of their position are not expanded.
+    min_share: f32,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        out: PathBuf::from("docs/precalc"),
+        positions: vec![],
+        depth: 0,
+        visits: EngineConfig::default().max_visits,
+        min_share: 0.1,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--out" => options.out = value()?.into(),
+            "--depth" => options.depth = value()?.parse().map_err(|_| "Invalid --depth")?,
+            "--visits" => options.visits = value()?.parse().map_err(|_| "Invalid --visits")?,
+            "--min-share" => {
+                options.min_share = value()?.parse().map_err(|_| "Invalid --min-share")?
+            }
+            "--positions" => {
+                let path = value()?;
+                let text = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("Could not read {}: {}", path, err))?;
+                options.positions.extend(
+                    text.lines()
+                        .map(str::trim)
+                        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                        .map(str::to_string),
+                );
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown argument {}", arg)),
+            _ => options.positions.push(arg),
+        }
+    }
+    if options.positions.is_empty() {
+        options.positions.push(START_POSITION.to_string());
+    }
+    Ok(options)
+}
+
+// Writes through a temporary file, so an interrupted build never leaves half a file behind.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(tmp, path)
+}
+
+// `to_precalc.json` is kept as json, so whatever else is in there survives a rebuild.
+struct Book {
+    dir: PathBuf,
+    json: serde_json::Value,
+    pre_calc: PreCalc,
+    manifest: BookManifest,
+    // Whether the folder had a packed book, which then is packed again at the end of the run.
+    packed: bool,
+}
+
+impl Book {
+    fn open(dir: &Path) -> Result<Book, BookError> {
+        std::fs::create_dir_all(dir.join("precalc"))?;
+        std::fs::create_dir_all(dir.join("partial"))?;
+        let json = match std::fs::read(dir.join("to_precalc.json")) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                serde_json::json!({ "scores_player_zero": {} })
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let pre_calc = serde_json::from_value(json.clone())?;
+        let manifest = if dir.join(MANIFEST_FILE).exists() {
+            read_manifest(dir)?
+        } else {
+            BookManifest::default()
+        };
+        Ok(Book {
+            dir: dir.to_path_buf(),
+            json,
+            pre_calc,
+            manifest,
+            packed: dir.join(PACKED_BOOK_FILE).exists(),
+        })
+    }
+
+    fn tree_path(&self, board: &Board) -> PathBuf {
+        self.dir
+            .join("precalc")
+            .join(format!("{}.mc_node", board.encode()))
+    }
+
+    fn partial_path(&self, board: &Board) -> PathBuf {
+        self.dir
+            .join("partial")
+            .join(format!("{}.mc_node", board.encode()))
+    }
+
+    fn read_tree(path: &Path) -> Option<MonteCarloTree> {
+        std::fs::read(path)
+            .ok()
+            .map(|bytes| MonteCarloTree::deserialize(&bytes))
+    }
+
+    fn is_complete(&self, board: &Board) -> bool {
+        self.json["scores_player_zero"]
+            .get(board.encode())
+            .is_some()
+            && self.manifest.trees.contains_key(&board.encode())
+    }
+
+    fn add(&mut self, board: &Board, tree: &MonteCarloTree) -> Result<(), BookError> {
+        match std::fs::remove_file(self.dir.join(PACKED_BOOK_FILE)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        let bytes = tree.serialize();
+        write_atomic(&self.tree_path(board), &bytes)?;
+        self.manifest
+            .trees
+            .insert(board.encode(), TreeEntry::new(&bytes));
+        write_atomic(
+            &self.dir.join(MANIFEST_FILE),
+            &serde_json::to_vec_pretty(&self.manifest)?,
+        )?;
+        self.json["scores_player_zero"][board.encode()] = win_rate_zero(board, tree).into();
+        write_atomic(
+            &self.dir.join("to_precalc.json"),
+            &serde_json::to_vec(&self.json)?,
+        )?;
+        // The partial tree is only removed once the position is in the book.
+        let _ = std::fs::remove_file(self.partial_path(board));
+        self.pre_calc = serde_json::from_value(self.json.clone())?;
+        Ok(())
+    }
+}
+
+// The scores of the tree are from the perspective of the player who made the last move.
+fn win_rate_zero(board: &Board, tree: &MonteCarloTree) -> f32 {
+    let score = tree.mc_node.scores();
+    let win_rate_last_move = score.0 as f32 / score.1.max(1) as f32;
+    if board.turn % 2 == 0 {
+        1.0 - win_rate_last_move
+    } else {
+        win_rate_last_move
+    }
+}
+
+// The position under which `board` goes into the book. Like `take_game_move`, a position that is already in the book
+// mirrored is looked up as that mirror image.
+fn canonical_position(book: &Book, board: &Board) -> Board {
+    match book_position(&book.pre_calc, board) {
+        Some((book_board, _, _)) => book_board,
+        None => board.clone(),
+    }
+}
+
+fn search(book: &Book, board: &Board, visits: u32) -> Result<MonteCarloTree, BookError> {
+    let mut ai_controlled_board = AIControlledBoard::decode(&board.encode())
+        .map_err(|err| format!("Invalid position {}: {:?}", board.encode(), err))?;
+    if let Some(tree) = Book::read_tree(&book.partial_path(board)) {
+        println!("  continuing from {} visits", tree.mc_node.number_visits());
+        ai_controlled_board.relevant_mc_tree = tree;
+    }
+    let mut next_checkpoint =
+        ai_controlled_board.relevant_mc_tree.mc_node.number_visits() + CHECKPOINT_VISITS;
+    while ai_controlled_board.relevant_mc_tree.mc_node.number_visits() < visits {
+        ai_controlled_board.ai_move(STEPS_PER_BATCH, &book.pre_calc);
+        let done = ai_controlled_board.relevant_mc_tree.mc_node.number_visits();
+        if done >= next_checkpoint {
+            write_atomic(
+                &book.partial_path(board),
+                &ai_controlled_board.relevant_mc_tree.serialize(),
+            )?;
+            println!("  {} visits", done);
+            next_checkpoint = done + CHECKPOINT_VISITS;
+        }
+    }
+    Ok(ai_controlled_board.relevant_mc_tree)
+}
+
+// The positions after the moves that got at least `min_share` of the visits.
+fn expand(board: &Board, tree: &mut MonteCarloTree, min_share: f32) -> Vec<Board> {
+    let visits = tree.mc_node.number_visits();
+    let Some(move_options) = tree.mc_node.move_options() else {
+        return vec![];
+    };
+    move_options
+        .iter()
+        .filter(|(_, child, _)| child.number_visits() as f32 >= visits as f32 * min_share)
+        .map(|(game_move, _, _)| {
+            let mut next = board.clone();
+            next.game_move(*game_move);
+            next
+        })
+        .collect()
+}
+
+fn build(options: &Options) -> Result<(), BookError> {
+    let mut book = Book::open(&options.out)?;
+    let mut queue = VecDeque::new();
+    for encoding in &options.positions {
+        let board = Board::decode(encoding)
+            .map_err(|err| format!("Invalid position {}: {:?}", encoding, err))?;
+        queue.push_back((board, 0));
+    }
+    let mut seen = HashSet::new();
+    while let Some((board, depth)) = queue.pop_front() {
+        let board = canonical_position(&book, &board);
+        if !seen.insert(board.encode()) || winner(&board).is_some() {
+            continue;
+        }
+        let mut tree = match Book::read_tree(&book.tree_path(&board)) {
+            // Built before, maybe by an interrupted run that did not get to write the score and the checksum.
+            Some(tree) => {
+                if !book.is_complete(&board) {
+                    book.add(&board, &tree)?;
+                }
+                tree
+            }
+            None => {
+                println!("{} (depth {})", board.encode(), depth);
+                let tree = search(&book, &board, options.visits)?;
+                book.add(&board, &tree)?;
+                println!(
+                    "  done, {} visits, white wins {:.1}%",
+                    tree.mc_node.number_visits(),
+                    win_rate_zero(&board, &tree) * 100.0
+                );
+                tree
+            }
+        };
+        if depth < options.depth {
+            for next in expand(&board, &mut tree, options.min_share) {
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    println!("Went through {} positions", seen.len());
+    if book.packed {
+        let report = pack_directory(&book.dir)?;
+        println!(
+            "Packed {} trees into {} ({} MB)",
+            report.trees,
+            book.dir.join(PACKED_BOOK_FILE).display(),
+            report.packed_size / 1_000_000
+        );
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: quoridor-book [--out DIR] [--depth N] [--visits N] [--min-share F] [--positions FILE] \
+                 [ENCODING...]"
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = build(&options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
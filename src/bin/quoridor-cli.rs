@@ -0,0 +1,153 @@
+// Play against the engine in the terminal, with the same settings as the web worker and the book read from disk.
+//
+//     cargo run --bin quoridor-cli -- --play black --book docs/precalc
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use quoridor_frontend::engine::*;
+use quoridor_frontend::game_result::{DrawReason, DrawRules, GameResult};
+use quoridor_frontend::native::*;
+use quoridor_frontend::text_board::*;
+
+const HELP: &str = "Enter moves in Quoridor Strats notation, `e2` moves your pawn and `d3h` or `d3v` places a wall.
+Other commands: `flip` turns the board around, `history` shows the moves so far, `quit` stops the game.";
+
+struct Options {
+    book: PathBuf,
+    ai_player: usize,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        book: PathBuf::from("docs/precalc"),
+        ai_player: 1,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--book" => {
+                options.book = args.next().ok_or("--book needs a directory")?.into();
+            }
+            "--play" => {
+                options.ai_player = match args.next().as_deref() {
+                    Some("white") => 1,
+                    Some("black") => 0,
+                    _ => return Err("--play needs `white` or `black`".to_string()),
+                };
+            }
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn player_name(player: usize) -> &'static str {
+    if player == 0 {
+        "WHITE"
+    } else {
+        "BLACK"
+    }
+}
+
+fn result_text(result: GameResult) -> String {
+    match result {
+        GameResult::Goal { winner } => format!("{} wins", player_name(winner)),
+        GameResult::Resigned { winner } => {
+            format!(
+                "{} resigns, {} wins",
+                player_name(1 - winner),
+                player_name(winner)
+            )
+        }
+        GameResult::Draw(DrawReason::MoveLimit) => {
+            format!("Draw after {} moves", DrawRules::default().max_moves)
+        }
+        GameResult::Draw(DrawReason::Repetition) => format!(
+            "Draw, the same position came back {} times",
+            DrawRules::default().repetitions
+        ),
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!("Usage: quoridor-cli [--play white|black] [--book DIR]");
+            std::process::exit(1);
+        }
+    };
+    let (commands, updates) = spawn_session(DirectoryBook::new(options.book.clone()));
+    commands
+        .send(UserCommand::SetAIPlayer(options.ai_player))
+        .unwrap();
+    let mut game = BoardWithHistory::new();
+    // Like on the web, the player who plays black sees their own pawn at the bottom.
+    let mut flipped = options.ai_player == 0;
+    let mut lines = io::stdin().lock().lines();
+    println!("{}", HELP);
+
+    loop {
+        println!();
+        println!("{}", render_board(&game.board, flipped));
+        if let Some(result) = game.result {
+            println!("{} ({})", result_text(result), result.score());
+            println!("{}", game.historic_moves());
+            return;
+        }
+
+        let to_move = game.board.turn % 2;
+        if to_move == options.ai_player {
+            loop {
+                match updates.recv() {
+                    Ok(CalculateUpdate::Progress(progress)) => {
+                        print!("\rThinking {:>3.0}%", progress * 100.0);
+                        let _ = io::stdout().flush();
+                    }
+                    Ok(CalculateUpdate::Finish(game_move)) => {
+                        println!(
+                            "\r{} plays {}",
+                            player_name(to_move),
+                            game_move.to_quoridor_strat_notation(&game.board)
+                        );
+                        game.game_move(game_move);
+                        break;
+                    }
+                    Ok(CalculateUpdate::Resign(player)) => {
+                        println!();
+                        game.resign(player);
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(_) => {
+                        eprintln!("The engine stopped");
+                        return;
+                    }
+                }
+            }
+            continue;
+        }
+
+        print!("{} to move> ", player_name(to_move));
+        let _ = io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return,
+        };
+        match line.trim() {
+            "" => {}
+            "quit" | "exit" => return,
+            "help" => println!("{}", HELP),
+            "flip" => flipped = !flipped,
+            "history" => println!("{}", game.historic_moves()),
+            notation => match parse_move(&game.board, notation) {
+                Some(game_move) => {
+                    game.game_move(game_move);
+                    commands.send(UserCommand::GameMove(game_move)).unwrap();
+                }
+                None => println!("{} is not a legal move, type `help` for help", notation),
+            },
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Draws a position as an SVG or PNG diagram, for the documentation. The format follows the extension of `--out`,
+// without `--out` the SVG is written to stdout.
+//
+//     cargo run --release --bin quoridor-diagram -- "0;10E1;10E9" --coordinates --caption --arrow e2 --out start.png
+use std::io::Write;
+use std::path::PathBuf;
+
+use quoridor::Board;
+use quoridor_frontend::diagram::{Diagram, DiagramOptions};
+use quoridor_frontend::text_board::parse_move;
+
+struct Options {
+    encoding: String,
+    out: Option<PathBuf>,
+    diagram: DiagramOptions,
+    // The arrow in notation, it can only be read once the board is known.
+    arrow: Option<String>,
+    // How many pixels a unit of the SVG is in the PNG.
+    scale: f32,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut encoding = None;
+    let mut options = Options {
+        encoding: String::new(),
+        out: None,
+        diagram: DiagramOptions::default(),
+        arrow: None,
+        scale: 1.0,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{} needs a value", name));
+        match arg.as_str() {
+            "--out" => options.out = Some(PathBuf::from(value("--out")?)),
+            "--arrow" => options.arrow = Some(value("--arrow")?),
+            "--scale" => {
+                options.scale = value("--scale")?
+                    .parse()
+                    .map_err(|err| format!("--scale: {}", err))?;
+                if !(options.scale.is_finite() && options.scale > 0.0) {
+                    return Err(format!("--scale: must be a positive number, got {}", options.scale));
+                }
+            }
+            "--coordinates" => options.diagram.coordinates = true,
+            "--caption" => options.diagram.caption = true,
+            "--flip" => options.diagram.flipped = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if encoding.is_none() => encoding = Some(arg),
+            _ => return Err(format!("Only one position per diagram, got {} too", arg)),
+        }
+    }
+    options.encoding = encoding.ok_or("No position given")?;
+    Ok(options)
+}
+
+fn draw(options: &Options) -> Result<(), String> {
+    let board = Board::decode(&options.encoding).map_err(|err| format!("{:?}", err))?;
+    let mut diagram_options = options.diagram;
+    if let Some(arrow) = &options.arrow {
+        diagram_options.arrow =
+            Some(parse_move(&board, arrow).ok_or(format!("{} is not a legal move here", arrow))?);
+    }
+    let diagram = Diagram::new(&board, &diagram_options);
+    match &options.out {
+        Some(out) => {
+            let is_png = out
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+            let bytes = if is_png {
+                diagram.to_png(options.scale)
+            } else {
+                diagram.to_svg().into_bytes()
+            };
+            std::fs::write(out, bytes).map_err(|err| format!("{}: {}", out.display(), err))
+        }
+        None => std::io::stdout()
+            .write_all(diagram.to_svg().as_bytes())
+            .map_err(|err| err.to_string()),
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: quoridor-diagram ENCODING [--out FILE.svg|FILE.png] [--coordinates] [--caption] [--flip] \
+                 [--arrow MOVE] [--scale F]"
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = draw(&options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,177 @@
+// Serves the engine over a UCI like text protocol on stdin and stdout, so GUIs, tournament managers and test
+// harnesses can drive it. Moves are in Quoridor Strats notation.
+//
+//     position startpos moves e2 e8
+//     position 4;9E3;10E7;D6h moves d2h
+//     go visits 600000 | go movetime 5000 | go infinite
+//     stop
+//
+// While searching the engine prints `info visits <n> winrate <w> pv <moves>`, the win rate is for the player to
+// move. A search always ends with `bestmove <move>`, or `bestmove (none)` when there is no move, like on a finished
+// position.
+//
+//     cargo run --bin quoridor-engine -- --book docs/precalc
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use quoridor::{Board, Move};
+use quoridor_frontend::engine::*;
+use quoridor_frontend::native::*;
+use quoridor_frontend::text_board::*;
+
+const START_POSITION: &str = "0;10E1;10E9";
+
+fn notation(board: &Board, moves: &[Move]) -> Vec<String> {
+    let mut board = board.clone();
+    let mut notation = vec![];
+    for game_move in moves {
+        notation.push(game_move.to_quoridor_strat_notation(&board));
+        board.game_move(*game_move);
+    }
+    notation
+}
+
+fn parse_go(args: &[&str]) -> Result<SearchLimit, String> {
+    match args {
+        // Without a limit we think as long as the web AI does.
+        [] => Ok(SearchLimit::Visits(600_000)),
+        ["infinite"] => Ok(SearchLimit::Infinite),
+        ["visits", visits] => visits
+            .parse()
+            .map(SearchLimit::Visits)
+            .map_err(|_| format!("Invalid number of visits {}", visits)),
+        ["movetime", millis] => millis
+            .parse()
+            .map(SearchLimit::MoveTime)
+            .map_err(|_| format!("Invalid movetime {}", millis)),
+        _ => Err(format!("Unknown go arguments {}", args.join(" "))),
+    }
+}
+
+// Returns the encoding to start from, and the moves from there, checked against the rules.
+fn parse_position(args: &[&str]) -> Result<(String, Board, Vec<Move>), String> {
+    let (encoding, rest) = match args {
+        ["startpos", rest @ ..] => (START_POSITION.to_string(), rest),
+        [encoding, rest @ ..] => (encoding.to_string(), rest),
+        [] => return Err("position needs `startpos` or an encoding".to_string()),
+    };
+    let mut board =
+        Board::decode(&encoding).map_err(|_| format!("Could not decode board {}", encoding))?;
+    let mut moves = vec![];
+    match rest {
+        [] => {}
+        ["moves", notations @ ..] => {
+            for notation in notations {
+                let game_move = parse_move(&board, notation)
+                    .ok_or_else(|| format!("Illegal move {}", notation))?;
+                board.game_move(game_move);
+                moves.push(game_move);
+            }
+        }
+        _ => return Err(format!("Expected `moves`, got {}", rest.join(" "))),
+    }
+    Ok((encoding, board, moves))
+}
+
+// The session thread only goes away when it panicked, then there is nothing left to answer the commands.
+fn send(commands: &Sender<UserCommand>, command: UserCommand) {
+    if commands.send(command).is_err() {
+        eprintln!("The engine stopped, quitting");
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let mut book = DirectoryBook::new("docs/precalc");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => {}
+        ["--book", dir] => book = DirectoryBook::new(dir),
+        _ => {
+            eprintln!("Usage: quoridor-engine [--book DIR]");
+            std::process::exit(1);
+        }
+    }
+
+    let (commands, updates) = spawn_session(book);
+    // The position of the last `position` command.
+    let mut position = Board::decode(START_POSITION).unwrap();
+    // The positions the searches started from that didn't print their `bestmove` yet, oldest first. The moves of a
+    // search are written in notation from its own position, also when a new `position` came in before it ended.
+    let searches: Arc<Mutex<VecDeque<Board>>> = Arc::new(Mutex::new(VecDeque::new()));
+
+    let printer_searches = searches.clone();
+    thread::spawn(move || {
+        for update in updates {
+            match update {
+                CalculateUpdate::Info(info) => {
+                    let Some(board) = printer_searches.lock().unwrap().front().cloned() else {
+                        continue;
+                    };
+                    println!(
+                        "info visits {} winrate {:.4} pv {}",
+                        info.visits,
+                        info.win_rate,
+                        notation(&board, &info.principal_variation).join(" ")
+                    )
+                }
+                CalculateUpdate::BestMove(game_move) => {
+                    let Some(board) = printer_searches.lock().unwrap().pop_front() else {
+                        continue;
+                    };
+                    match game_move {
+                        Some(game_move) => {
+                            println!("bestmove {}", game_move.to_quoridor_strat_notation(&board))
+                        }
+                        None => println!("bestmove (none)"),
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] => {}
+            ["uci"] => {
+                println!("id name quoridor_frontend");
+                println!("uciok");
+            }
+            ["isready"] => println!("readyok"),
+            ["ucinewgame"] => {}
+            ["position", ref args @ ..] => match parse_position(args) {
+                Ok((encoding, board, moves)) => {
+                    // A new position ends the search, which still prints its `bestmove`. Without a search running
+                    // the stop does nothing.
+                    send(&commands, UserCommand::Stop);
+                    position = board;
+                    send(&commands, UserCommand::DecodeBoard(encoding));
+                    for game_move in moves {
+                        send(&commands, UserCommand::GameMove(game_move));
+                    }
+                }
+                Err(err) => println!("info string {}", err),
+            },
+            ["go", ref args @ ..] => match parse_go(args) {
+                Ok(limit) => {
+                    // Every search ends with one `bestmove`, also one that is replaced by this one.
+                    send(&commands, UserCommand::Stop);
+                    searches.lock().unwrap().push_back(position.clone());
+                    send(&commands, UserCommand::Go(limit));
+                }
+                Err(err) => println!("info string {}", err),
+            },
+            ["stop"] => send(&commands, UserCommand::Stop),
+            ["quit"] => break,
+            _ => println!("info string Unknown command {}", line),
+        }
+    }
+}
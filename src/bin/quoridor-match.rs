@@ -0,0 +1,346 @@
+// Plays two engine configurations against each other, to measure what a change to the thresholds in `EngineConfig`
+// does to the strength of the AI. The engines alternate colors, and with `--openings` every pair of games starts from
+// a position of the book. All games are written in notation to `--out`.
+//
+//     cargo run --release --bin quoridor-match -- --games 200 --threads 8 --openings \
+//         --a visits=600000,simulations=300000 --b visits=300000,simulations=150000,book=false
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::thread;
+
+use quoridor::Board;
+use quoridor_frontend::engine::*;
+use quoridor_frontend::game_result::{DrawReason, DrawRules, GameResult};
+use quoridor_frontend::native::*;
+
+const START_POSITION: &str = "0;10E1;10E9";
+const ENGINE_NAMES: [&str; 2] = ["A", "B"];
+
+struct Options {
+    games: usize,
+    threads: usize,
+    book: PathBuf,
+    configs: [EngineConfig; 2],
+    openings: bool,
+    // Games that take longer than this are a draw, like a position that comes back three times.
+    max_moves: usize,
+    out: Option<PathBuf>,
+}
+
+fn parse_config(text: &str) -> Result<EngineConfig, String> {
+    let mut config = EngineConfig::default();
+    for setting in text.split(',').filter(|setting| !setting.is_empty()) {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Expected key=value, got {}", setting))?;
+        let invalid = || format!("Invalid value for {}: {}", key, value);
+        match key {
+            "visits" => config.move_visits = value.parse().map_err(|_| invalid())?,
+            "simulations" => config.move_simulations = value.parse().map_err(|_| invalid())?,
+            "max_visits" => config.max_visits = value.parse().map_err(|_| invalid())?,
+            "book" => config.use_book = value.parse().map_err(|_| invalid())?,
+            "resign_win_rate" => config.resign_win_rate = value.parse().map_err(|_| invalid())?,
+            "resign_moves" => config.resign_moves = value.parse().map_err(|_| invalid())?,
+            "resign_visits" => config.resign_visits = value.parse().map_err(|_| invalid())?,
+            "variety_moves" => config.variety.moves = value.parse().map_err(|_| invalid())?,
+            "variety_temperature" => {
+                config.variety.temperature = value.parse().map_err(|_| invalid())?
+            }
+            "variety_max_loss" => config.variety.max_loss = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("Unknown setting {}", key)),
+        }
+    }
+    Ok(config)
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: 100,
+        threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        book: PathBuf::from("docs/precalc"),
+        configs: [EngineConfig::default(), EngineConfig::default()],
+        openings: false,
+        max_moves: 200,
+        out: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--games" => options.games = value()?.parse().map_err(|_| "Invalid --games")?,
+            "--threads" => options.threads = value()?.parse().map_err(|_| "Invalid --threads")?,
+            "--max-moves" => {
+                options.max_moves = value()?.parse().map_err(|_| "Invalid --max-moves")?
+            }
+            "--book" => options.book = value()?.into(),
+            "--out" => options.out = Some(value()?.into()),
+            "--a" => options.configs[0] = parse_config(&value()?)?,
+            "--b" => options.configs[1] = parse_config(&value()?)?,
+            "--openings" => options.openings = true,
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+// The positions of the book, sorted so every run uses the same openings in the same order.
+fn load_openings(book: &Path) -> Result<Vec<String>, BookError> {
+    let json: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(book.join("to_precalc.json"))?)?;
+    let mut openings: Vec<String> = json["scores_player_zero"]
+        .as_object()
+        .ok_or("to_precalc.json has no scores_player_zero")?
+        .keys()
+        .cloned()
+        .collect();
+    openings.sort();
+    Ok(openings)
+}
+
+struct GameRecord {
+    index: usize,
+    // Which engine played white.
+    white_engine: usize,
+    opening: String,
+    moves: Vec<String>,
+    result: GameResult,
+}
+
+impl GameRecord {
+    // The score of engine A, 0.5 for a draw.
+    fn score_a(&self) -> f64 {
+        match self.result.winner() {
+            Some(color) if (color == 0) == (self.white_engine == 0) => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        }
+    }
+}
+
+async fn new_session(
+    options: &Options,
+    book: &SharedBook,
+    engine: usize,
+    player: usize,
+    opening: &str,
+) -> EngineSession<VecCommands, DiscardUpdates, SharedBook> {
+    let mut session = EngineSession::with_config(
+        VecCommands {
+            commands: Default::default(),
+        },
+        DiscardUpdates,
+        book.clone(),
+        options.configs[engine],
+    )
+    .await;
+    session
+        .handle_command(UserCommand::DecodeBoard(opening.to_string()))
+        .await;
+    session
+        .handle_command(UserCommand::SetAIPlayer(player))
+        .await;
+    session
+}
+
+async fn play_game(
+    options: &Options,
+    book: &SharedBook,
+    index: usize,
+    opening: String,
+) -> GameRecord {
+    let white_engine = index % 2;
+    let engines = [white_engine, 1 - white_engine];
+    let mut sessions = [
+        new_session(options, book, engines[0], 0, &opening).await,
+        new_session(options, book, engines[1], 1, &opening).await,
+    ];
+    let start = Board::decode(&opening).unwrap();
+    let mut board = start.clone();
+    let rules = DrawRules {
+        max_moves: options.max_moves,
+        ..DrawRules::default()
+    };
+    let mut moves = vec![];
+    let mut played = vec![];
+    let result = loop {
+        if let Some(result) = rules.result(&start, &played) {
+            break result;
+        }
+        let to_move = board.turn % 2;
+        let mut new_command = true;
+        let game_move = loop {
+            if let Some(game_move) = sessions[to_move].search_batch(new_command).await {
+                break Some(game_move);
+            }
+            if sessions[to_move].resigned.is_some() {
+                break None;
+            }
+            new_command = false;
+        };
+        let Some(game_move) = game_move else {
+            break GameResult::Resigned {
+                winner: 1 - to_move,
+            };
+        };
+        moves.push(game_move.to_quoridor_strat_notation(&board));
+        played.push(game_move);
+        board.game_move(game_move);
+        sessions[1 - to_move]
+            .handle_command(UserCommand::GameMove(game_move))
+            .await;
+    };
+    GameRecord {
+        index,
+        white_engine,
+        opening,
+        moves,
+        result,
+    }
+}
+
+fn elo(score: f64) -> f64 {
+    let score = score.clamp(0.001, 0.999);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn report(records: &[GameRecord]) {
+    let games = records.len() as f64;
+    let scores: Vec<f64> = records.iter().map(GameRecord::score_a).collect();
+    let mean = scores.iter().sum::<f64>() / games;
+    let variance = scores
+        .iter()
+        .map(|score| (score - mean).powi(2))
+        .sum::<f64>()
+        / games;
+    let standard_error = (variance / games).sqrt();
+    let draws = records
+        .iter()
+        .filter(|record| record.result.winner().is_none())
+        .count();
+    let repetitions = records
+        .iter()
+        .filter(|record| record.result == GameResult::Draw(DrawReason::Repetition))
+        .count();
+    let resignations = records
+        .iter()
+        .filter(|record| matches!(record.result, GameResult::Resigned { .. }))
+        .count();
+    let a_wins = records
+        .iter()
+        .filter(|record| record.score_a() == 1.0)
+        .count();
+    let a_white_wins = records
+        .iter()
+        .filter(|record| record.white_engine == 0 && record.score_a() == 1.0)
+        .count();
+    let white_wins = records
+        .iter()
+        .filter(|record| record.result.winner() == Some(0))
+        .count();
+
+    println!();
+    println!("Games: {}", records.len());
+    println!(
+        "A wins {}, B wins {}, draws {}",
+        a_wins,
+        records.len() - a_wins - draws,
+        draws
+    );
+    println!(
+        "{} games were resigned, {} draws by repetition",
+        resignations, repetitions
+    );
+    println!(
+        "A wins {} as white and {} as black, white wins {:.1}% of all games",
+        a_white_wins,
+        a_wins - a_white_wins,
+        white_wins as f64 / games * 100.0
+    );
+    println!(
+        "Score A {:.1}%, Elo difference {:+.0} (95% confidence {:+.0} to {:+.0})",
+        mean * 100.0,
+        elo(mean),
+        elo(mean - 1.96 * standard_error),
+        elo(mean + 1.96 * standard_error)
+    );
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: quoridor-match [--games N] [--threads N] [--book DIR] [--openings] [--max-moves N] \
+                 [--out FILE] [--a key=value,...] [--b key=value,...]"
+            );
+            eprintln!(
+                "Settings are visits, simulations, max_visits, book, resign_win_rate, resign_moves, \
+                 resign_visits, variety_moves, variety_temperature and variety_max_loss"
+            );
+            std::process::exit(1);
+        }
+    };
+    let openings = if options.openings {
+        match load_openings(&options.book) {
+            Ok(openings) => openings,
+            Err(err) => {
+                eprintln!("Could not load the openings: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        vec![START_POSITION.to_string()]
+    };
+    let mut out: Box<dyn Write> = match &options.out {
+        Some(path) => Box::new(File::create(path).expect("Could not create the output file")),
+        None => Box::new(std::io::sink()),
+    };
+    println!("A: {:?}", options.configs[0]);
+    println!("B: {:?}", options.configs[1]);
+
+    let next_game = AtomicUsize::new(0);
+    let (record_sender, record_receiver) = channel();
+    let mut records = vec![];
+    thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            let record_sender = record_sender.clone();
+            let (options, openings, next_game) = (&options, &openings, &next_game);
+            scope.spawn(move || {
+                // Read once per thread, all games on the thread share it.
+                let book = SharedBook::new(options.book.clone());
+                loop {
+                    let index = next_game.fetch_add(1, Ordering::SeqCst);
+                    if index >= options.games {
+                        break;
+                    }
+                    // Both engines get every opening once as white.
+                    let opening = openings[(index / 2) % openings.len()].clone();
+                    let record =
+                        futures::executor::block_on(play_game(options, &book, index, opening));
+                    record_sender.send(record).unwrap();
+                }
+            });
+        }
+        drop(record_sender);
+
+        for record in record_receiver {
+            let line = format!(
+                "{} {} (white) vs {} (black) {} {} {}",
+                record.index,
+                ENGINE_NAMES[record.white_engine],
+                ENGINE_NAMES[1 - record.white_engine],
+                record.opening,
+                record.result.score(),
+                record.moves.join(";")
+            );
+            println!("{}", line);
+            writeln!(out, "{}", line).expect("Could not write the game");
+            records.push(record);
+        }
+    });
+    report(&records);
+}
@@ -0,0 +1,33 @@
+// Packs the trees of a book folder like `docs/precalc` into the single file `book.qbook` the worker reads, see
+// `packed_book` for the format. Only the positions listed in `to_precalc.json` whose tree matches the manifest are
+// packed.
+//
+//     cargo run --release --bin quoridor-pack -- docs/precalc
+use std::path::PathBuf;
+
+use quoridor_frontend::native::pack_directory;
+use quoridor_frontend::packed_book::PACKED_BOOK_FILE;
+
+fn main() {
+    let dir = std::env::args()
+        .nth(1)
+        .map_or_else(|| PathBuf::from("docs/precalc"), PathBuf::from);
+    match pack_directory(&dir) {
+        Ok(report) => {
+            for skipped in &report.skipped {
+                eprintln!("Skipping {}", skipped);
+            }
+            println!(
+                "Packed {} trees of {} MB into {} ({} MB)",
+                report.trees,
+                report.unpacked_size / 1_000_000,
+                dir.join(PACKED_BOOK_FILE).display(),
+                report.packed_size / 1_000_000
+            );
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+// Mines puzzles from recorded games, like the `--out` file of `quoridor-match` or files with one game per line in
+// notation. Every position of every game is searched. A position becomes a puzzle when the move before it threw the
+// game away, the same swing the worker reports as a seen table, and only one move keeps the win. New puzzles are added
+// to `docs/puzzles.json`, the puzzle page is built with that file.
+//
+//     cargo run --release --bin quoridor-puzzles -- games.txt --visits 1000000
+use std::path::PathBuf;
+
+use quoridor::{AIControlledBoard, Board, PreCalc};
+use quoridor_frontend::engine::{principal_variation, BookError, BookFetcher};
+use quoridor_frontend::grid::winner;
+use quoridor_frontend::native::DirectoryBook;
+use quoridor_frontend::puzzle::{Puzzle, PuzzleSet, PUZZLES_FILE};
+use quoridor_frontend::text_board::parse_move;
+
+const START_POSITION: &str = "0;10E1;10E9";
+const STEPS_PER_BATCH: usize = 100_000;
+// Moves with fewer visits than this share of the position are not compared, their win rate says too little.
+const MIN_CHILD_SHARE: f32 = 0.01;
+
+struct Options {
+    games: Vec<PathBuf>,
+    out: PathBuf,
+    book: PathBuf,
+    visits: u32,
+    // The player to move has to win at least this often...
+    min_win_rate: f32,
+    // ...after the move before lost the opponent at least this much...
+    min_swing: f32,
+    // ...and the best move has to be this much better than the second best.
+    min_gap: f32,
+    // The number of moves of the solution, the moves of the defense included.
+    line_length: usize,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: vec![],
+        out: PathBuf::from(PUZZLES_FILE),
+        book: PathBuf::from("docs/precalc"),
+        visits: 1_000_000,
+        // The worker reports a seen table below 0.4 for the AI, so from the other side this is above 0.6.
+        min_win_rate: 0.6,
+        min_swing: 0.3,
+        min_gap: 0.15,
+        line_length: 3,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--out" => options.out = value()?.into(),
+            "--book" => options.book = value()?.into(),
+            "--visits" => options.visits = value()?.parse().map_err(|_| "Invalid --visits")?,
+            "--min-win-rate" => {
+                options.min_win_rate = value()?.parse().map_err(|_| "Invalid --min-win-rate")?
+            }
+            "--min-swing" => {
+                options.min_swing = value()?.parse().map_err(|_| "Invalid --min-swing")?
+            }
+            "--min-gap" => options.min_gap = value()?.parse().map_err(|_| "Invalid --min-gap")?,
+            "--line" => {
+                options.line_length = value()?.parse().map_err(|_| "Invalid --line")?;
+                if options.line_length == 0 {
+                    return Err("--line needs at least one move".to_string());
+                }
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown argument {}", arg)),
+            _ => options.games.push(arg.into()),
+        }
+    }
+    if options.games.is_empty() {
+        return Err("No game files given".to_string());
+    }
+    Ok(options)
+}
+
+struct RecordedGame {
+    start: String,
+    moves: Vec<String>,
+}
+
+// Reads a line of `quoridor-match`, or a line with only the moves of a game from the start position.
+fn parse_game(line: &str) -> Option<RecordedGame> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (start, moves) = if words.len() >= 8 && words[3] == "vs" {
+        (words[6], words.get(8).copied().unwrap_or(""))
+    } else {
+        (START_POSITION, line)
+    };
+    Some(RecordedGame {
+        start: start.to_string(),
+        moves: moves
+            .split([';', ' '])
+            .filter(|notation| !notation.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+struct Evaluation {
+    // The chance the player to move wins.
+    win_rate: f32,
+    // How much better the best move is than the second best, for the player to move.
+    gap: f32,
+    line: Vec<String>,
+}
+
+fn evaluate(board: &Board, options: &Options, pre_calc: &PreCalc) -> Option<Evaluation> {
+    if winner(board).is_some() {
+        return None;
+    }
+    let mut ai_controlled_board = AIControlledBoard::decode(&board.encode()).ok()?;
+    while ai_controlled_board.relevant_mc_tree.mc_node.number_visits() < options.visits
+        && !ai_controlled_board.is_played_out()
+    {
+        ai_controlled_board.ai_move(STEPS_PER_BATCH, pre_calc);
+    }
+    let mc_node = &mut ai_controlled_board.relevant_mc_tree.mc_node;
+    let score = mc_node.scores();
+    // The scores are from the perspective of the player who made the last move.
+    let win_rate = 1.0 - score.0 as f32 / score.1.max(1) as f32;
+    let min_visits = (score.1 as f32 * MIN_CHILD_SHARE) as u32;
+    let mut move_win_rates: Vec<f32> = mc_node
+        .move_options()
+        .map(|move_options| {
+            move_options
+                .iter()
+                .filter(|(_, child, _)| child.number_visits() > min_visits)
+                .map(|(_, child, _)| {
+                    let score = child.scores();
+                    score.0 as f32 / score.1.max(1) as f32
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    move_win_rates.sort_by(|a, b| b.total_cmp(a));
+    let gap = match move_win_rates.as_slice() {
+        [best, second, ..] => best - second,
+        [best] => *best,
+        [] => 0.0,
+    };
+
+    let mut line_board = board.clone();
+    let mut line = vec![];
+    for game_move in principal_variation(mc_node, options.line_length) {
+        line.push(game_move.to_quoridor_strat_notation(&line_board));
+        line_board.game_move(game_move);
+    }
+    Some(Evaluation {
+        win_rate,
+        gap,
+        line,
+    })
+}
+
+fn mine_game(
+    game: &RecordedGame,
+    options: &Options,
+    pre_calc: &PreCalc,
+    puzzle_set: &PuzzleSet,
+) -> Result<Vec<Puzzle>, BookError> {
+    let mut board = Board::decode(&game.start).map_err(|err| format!("{:?}", err))?;
+    let mut puzzles = vec![];
+    let mut previous: Option<Evaluation> = None;
+    for index in 0..=game.moves.len() {
+        let evaluation = evaluate(&board, options, pre_calc);
+        if let (Some(before), Some(now)) = (&previous, &evaluation) {
+            // `before` is for the opponent, who just made its move.
+            let swing = before.win_rate - (1.0 - now.win_rate);
+            if swing >= options.min_swing
+                && now.win_rate >= options.min_win_rate
+                && now.gap >= options.min_gap
+                && !now.line.is_empty()
+                && !puzzle_set.contains(&board)
+                && !puzzles
+                    .iter()
+                    .any(|puzzle: &Puzzle| puzzle.encoding == board.encode())
+            {
+                println!(
+                    "{} wins {:.0}% with {}",
+                    board.encode(),
+                    now.win_rate * 100.0,
+                    now.line.join(" ")
+                );
+                puzzles.push(Puzzle {
+                    encoding: board.encode(),
+                    to_move: board.turn % 2,
+                    solutions: vec![now.line.clone()],
+                    win_rate: now.win_rate,
+                });
+            }
+        }
+        previous = evaluation;
+        let Some(notation) = game.moves.get(index) else {
+            break;
+        };
+        let game_move = parse_move(&board, notation)
+            .ok_or_else(|| format!("illegal move {} in {}", notation, board.encode()))?;
+        board.game_move(game_move);
+    }
+    Ok(puzzles)
+}
+
+fn mine(options: &Options) -> Result<(), BookError> {
+    let pre_calc = match futures::executor::block_on(
+        DirectoryBook::new(options.book.clone()).board_scores(),
+    ) {
+        Ok(pre_calc) => pre_calc,
+        Err(err) => {
+            eprintln!("Searching without the book: {}", err);
+            PreCalc::new().into()
+        }
+    };
+    let mut puzzle_set = match std::fs::read_to_string(&options.out) {
+        Ok(json) => PuzzleSet::parse(&json)?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => PuzzleSet::default(),
+        Err(err) => return Err(err.into()),
+    };
+    let known = puzzle_set.puzzles.len();
+
+    for path in &options.games {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+        for (number, line) in text.lines().enumerate() {
+            let Some(game) = parse_game(line) else {
+                continue;
+            };
+            match mine_game(&game, options, &pre_calc, &puzzle_set) {
+                Ok(puzzles) => puzzle_set.puzzles.extend(puzzles),
+                // One broken game shouldn't cost the puzzles of all the others.
+                Err(err) => eprintln!("Skipping {}:{}: {}", path.display(), number + 1, err),
+            }
+            // Written after every game, mining takes long and may be interrupted.
+            let tmp = options.out.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_string_pretty(&puzzle_set)?)?;
+            std::fs::rename(tmp, &options.out)?;
+        }
+    }
+    println!(
+        "Found {} new puzzles, {} has {} puzzles",
+        puzzle_set.puzzles.len() - known,
+        options.out.display(),
+        puzzle_set.puzzles.len()
+    );
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: quoridor-puzzles GAMES... [--out FILE] [--book DIR] [--visits N] [--min-win-rate F] \
+                 [--min-swing F] [--min-gap F] [--line N]"
+            );
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = mine(&options) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
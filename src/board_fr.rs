@@ -3,12 +3,23 @@ use log::info;
 
 use quoridor::*;
 
-use crate::calc_worker::*;
-
-const DIMENSION: usize = 9;
-
-
-#[derive(Clone, Copy)]
+use quoridor_frontend::game_result::{DrawReason, GameResult};
+use quoridor_frontend::grid::*;
+use quoridor_frontend::i18n::Locale;
+use quoridor_frontend::share::SharedLink;
+use quoridor_frontend::text_board::{legal_moves, parse_move};
+
+use crate::analysis_report::AnalysisReport;
+use crate::board_svg::BoardSvg;
+use crate::diagram_export::DiagramExport;
+use crate::win_rate_graph::WinRateGraph;
+use crate::quoridor_worker::*;
+use crate::share_link::ShareLink;
+use crate::settings::{use_locale, use_settings};
+use crate::theme::Theme;
+
+
+#[derive(Clone, Copy, PartialEq)]
 enum HoverState {
     VerticalWall(usize, usize),
     HorizontalWall(usize, usize),
@@ -28,119 +39,42 @@ impl HoverState {
             HoverState::Pawn(r, c) => *r == row && *c == col,
         }
     }
-}
-
 
-fn part_of_walls(
-    square_type: SquareType,
-    row: usize,
-    col: usize,
-) -> Vec<(WallDirection, Position)> {
-    let row = row as i8;
-    let col = col as i8;
-    let mut walls = vec![];
-    match square_type {
-        SquareType::VerticalBorder => {
-            if col / 2 >= 8 {
-                return vec![];
-            }
-            if row / 2 >= 1 {
-                walls.push((
-                    WallDirection::Vertical,
-                    Position {
-                        row: row / 2 - 1,
-                        col: col / 2,
-                    },
-                ));
-            };
-            if row / 2 < 8 {
-                walls.push((
-                    WallDirection::Vertical,
-                    Position {
-                        row: row / 2,
-                        col: col / 2,
-                    },
-                ));
-            };
-        }
-        SquareType::HorizontalBorder => {
-            if row / 2 >= 8 {
-                return vec![];
-            }
-            if col / 2 >= 1 {
-                walls.push((
-                    WallDirection::Horizontal,
-                    Position {
-                        row: row / 2,
-                        col: col / 2 - 1,
-                    },
-                ));
-            }
-            if col / 2 < 8 {
-                walls.push((
-                    WallDirection::Horizontal,
-                    Position {
-                        row: row / 2,
-                        col: col / 2,
-                    },
-                ));
-            };
-        }
-        SquareType::Corner => {
-            walls.push((
-                WallDirection::Horizontal,
-                Position {
-                    row: row / 2,
-                    col: col / 2,
-                },
-            ));
-            walls.push((
-                WallDirection::Vertical,
-                Position {
-                    row: row / 2,
-                    col: col / 2,
-                },
-            ));
-        }
-        _ => (),
-    };
-    walls
-}
-
-fn is_part_of_wall(board: &Board, square_type: SquareType, row: usize, col: usize) -> bool {
-    let walls = part_of_walls(square_type, row, col);
-    for wall in walls {
-        if board.walls.is_allowed(wall.0, wall.1) {
-            return true;
-        }
+    fn wall_move(&self) -> Option<Move> {
+        let (direction, r, c) = match *self {
+            HoverState::VerticalWall(r, c) => (WallDirection::Vertical, r, c),
+            HoverState::HorizontalWall(r, c) => (WallDirection::Horizontal, r, c),
+            HoverState::Pawn(..) => return None,
+        };
+        Some(Move::Wall(direction, Position { row: (r / 2) as i8, col: (c / 2) as i8 }))
     }
-    false
 }
 
 
-
-
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
-pub enum SquareType {
-    Square,
-    VerticalBorder,
-    HorizontalBorder,
-    Corner,
+// Layout of the squares in the display grid. The sizes come from the `--square` and `--groove` variables of the board,
+// so the board scales with the screen.
+trait SquareLayout {
+    fn size(&self) -> &'static str;
+    // Grooves are thin, on a touch screen they are hard to hit. This reaches into the squares next to them.
+    fn hit_area(&self) -> Option<&'static str>;
+    fn hover_state(&self, row: usize, col: usize) -> Option<HoverState>;
 }
 
-impl SquareType {
-    fn width(&self) -> &'static str {
+impl SquareLayout for SquareType {
+    fn size(&self) -> &'static str {
         match self {
-            SquareType::Square => "w-16",
-            SquareType::HorizontalBorder => "w-16",
-            _ => "w-4",
+            SquareType::Square => "width: var(--square); height: var(--square);",
+            SquareType::HorizontalBorder => "width: var(--square); height: var(--groove);",
+            SquareType::VerticalBorder => "width: var(--groove); height: var(--square);",
+            SquareType::Corner => "width: var(--groove); height: var(--groove);",
         }
     }
-    fn height(&self) -> &'static str {
+
+    fn hit_area(&self) -> Option<&'static str> {
         match self {
-            SquareType::Square => "h-16",
-            SquareType::VerticalBorder => "h-16",
-            _ => "h-4",
+            SquareType::HorizontalBorder => Some("position: absolute; left: 0; right: 0; top: calc(var(--groove) * -0.75); bottom: calc(var(--groove) * -0.75); z-index: 5;"),
+            SquareType::VerticalBorder => Some("position: absolute; top: 0; bottom: 0; left: calc(var(--groove) * -0.75); right: calc(var(--groove) * -0.75); z-index: 5;"),
+            _ => None,
         }
     }
 
@@ -162,199 +96,605 @@ impl SquareType {
 }
 
 
-pub fn QuoridorBoard(cx: Scope) -> Element {
-    let rows = DIMENSION * 2 - 1;
-    let cols = DIMENSION * 2 - 1;
+#[derive(Props)]
+pub struct BoardGridProps<'a> {
+    pub(crate) board: Board,
+    pub(crate) flipped: bool,
+    // Whether the user can make a move on this board right now.
+    pub(crate) interactive: bool,
+    pub(crate) on_move: EventHandler<'a, Move>,
+    // The last move of each side, the latest last. They are pointed out on the board.
+    #[props(default)]
+    pub(crate) highlights: Vec<MoveHighlight>,
+}
 
-    let hover_state: &UseState<Option<HoverState>> = use_state(&cx, || None);
-    let ai_suggest_move: &UseState<Option<(Move, (usize, usize))>> = use_state(&cx, || None);
-    let board_flipped = use_state(&cx, || false);
+// Fills the width of a phone, and stops growing at 4rem squares. The 9 squares and 8 grooves of a quarter square are 11
+// squares wide.
+pub(crate) const BOARD_SIZE: &str = "--square: min(4rem, calc((100vw - 2rem) / 11)); --groove: calc(var(--square) / 4); touch-action: manipulation;";
+// The distance between two squares of the grid, a square and a groove.
+const SQUARE_PITCH: f32 = 1.25;
+
+pub(crate) const MOVE_ANIMATIONS: &str = "@keyframes pawn-slide { from { transform: translate(var(--dx), var(--dy)); } } \
+    @keyframes wall-drop { from { opacity: 0; transform: scale(1.5); } } \
+    @media (prefers-reduced-motion: reduce) { .board * { animation: none !important; } }";
+
+// Jumps are the rule new players don't expect, so the board says why such a move is offered.
+pub(crate) fn jump_explanation(locale: Locale, pawn_move: (PawnMove, Option<PawnMove>)) -> &'static str {
+    match pawn_move.1 {
+        Some(second) if second == pawn_move.0 => locale.text("jump_straight"),
+        Some(_) => locale.text("jump_diagonal"),
+        None => "",
+    }
+}
 
-    let (worker, calc_update, board, ai_player) = use_webworker(cx);
-    let progress = match &calc_update.get() {
-        CalculateUpdate::Progress(progress) => (progress * 100.0).round() ,
-        CalculateUpdate::Finish(_) => 0.0,
-    };
+// The ring of a pawn, drawn inside it so the pawn keeps its size.
+fn outline_style(outline: Option<&str>) -> String {
+    outline.map(|color| format!("border: 3px solid {color};")).unwrap_or_default()
+}
 
+// Slides the pawn of the latest move in from the square it came from.
+fn pawn_slide_style(latest: Option<&MoveHighlight>, row: usize, col: usize, flipped: bool) -> String {
+    match latest {
+        Some(latest @ MoveHighlight::Pawn { from, to }) if latest.is_destination(row, col) => {
+            let flip = if flipped { -1.0 } else { 1.0 };
+            format!(
+                "--dx: calc(var(--square) * {}); --dy: calc(var(--square) * {}); animation: pawn-slide 300ms ease-out; \
+                 position: relative; z-index: 10;",
+                (from.col - to.col) as f32 * SQUARE_PITCH,
+                (from.row - to.row) as f32 * SQUARE_PITCH * flip,
+            )
+        }
+        _ => String::new(),
+    }
+}
 
+// The board, drawn as an SVG or with the grid of divs, whichever the settings ask for. Moves the user makes are passed
+// to `on_move`, it's up to the parent to apply them.
+pub fn BoardGrid<'a>(cx: Scope<'a, BoardGridProps<'a>>) -> Element<'a> {
+    let props = cx.props;
+    if use_settings(cx).svg_board {
+        render! {
+            BoardSvg {
+                board: props.board.clone(),
+                flipped: props.flipped,
+                interactive: props.interactive,
+                highlights: props.highlights.clone(),
+                on_move: move |game_move: Move| props.on_move.call(game_move),
+            }
+        }
+    } else {
+        render! {
+            BoardDivs {
+                board: props.board.clone(),
+                flipped: props.flipped,
+                interactive: props.interactive,
+                highlights: props.highlights.clone(),
+                on_move: move |game_move: Move| props.on_move.call(game_move),
+            }
+        }
+    }
+}
+
+// The 17x17 grid of squares and grooves as divs, the board as it was drawn before the SVG board.
+fn BoardDivs<'a>(cx: Scope<'a, BoardGridProps<'a>>) -> Element<'a> {
+    let rows = DIMENSION * 2 - 1;
+    let cols = DIMENSION * 2 - 1;
 
-    let rows: Vec<usize>= match board_flipped.get() {
+    let hover_state: &UseState<Option<HoverState>> = use_state(cx, || None);
+    // On a touch screen the first tap on a groove only shows the wall, a second tap on it places it. The wall is kept
+    // with the turn it was shown in, after a move it's gone.
+    let touch_input = use_state(cx, || false);
+    let pending_wall: &UseState<Option<(usize, HoverState)>> = use_state(cx, || None);
+    let board = &cx.props.board;
+    let interactive = cx.props.interactive;
+    let on_move = &cx.props.on_move;
+    let highlights = &cx.props.highlights;
+    let flipped = cx.props.flipped;
+    let settings = use_settings(cx);
+    let locale = use_locale(cx);
+    let Theme {
+        board: board_color,
+        square,
+        groove,
+        wall,
+        hover_wall,
+        pawns,
+        pawn_previews,
+        pawn_outlines,
+        last_move_origin,
+        last_move_destination,
+        last_wall,
+        ..
+    } = settings.theme.theme();
+    // Only the latest move is animated, the one before already was.
+    let latest = highlights.last().filter(|_| settings.animate_moves);
+    let move_animations = MOVE_ANIMATIONS;
+    let board_size = BOARD_SIZE;
+    let pending_wall_now = pending_wall.get().filter(|(turn, _)| *turn == board.turn).map(|(_, wall)| wall);
+    let wall_pending = interactive && pending_wall_now.is_some();
+
+    let rows: Vec<usize>= match cx.props.flipped {
         true => (0..rows).rev().collect(),
         false => (0..rows).collect(),
     };
-    let encoding: &UseState<String> = use_state(&cx, || "".to_string());
-
-
-
-    let current_ai_player = *ai_player.get();
-    let players_turn = current_ai_player.is_some() && board.read().board.turn % 2 != current_ai_player.unwrap();
-    let hover_square = hover_state.get().clone();
+    let hover_square = *hover_state.get();
     cx.render(rsx! {
-        div { class: "flex justify-center items-start space-x-4",
         div { class: "flex flex-col items-center",
-            div {
-                class: "board bg-gray-100 p-4 grid grid-cols-{cols}",
-                rows.clone().into_iter().map(|row| {
-                    rsx! {
-                        div {
-                            class: "flex",
-                            (0..cols).map(|col| {
-                                let square_type = match (row%2 == 0, col%2 ==0 ) {
-                                    (true,true) => SquareType::Square,
-                                    (true,false) => SquareType::VerticalBorder,
-                                    (false,true) => SquareType::HorizontalBorder,
-                                    (false,false) => SquareType::Corner,
-                                };
-
-                                let mut color = if col % 2 == 0 && row % 2 == 0 { "bg-amber-600" } else { "bg-amber-400" };
-                                //if board.read().board.is_wall_probable_front_end(square_type, row,col) {
-                                //        color = "bg-gray-500";
-                                //}
-
-                                if let Some(hover_state) = hover_state.get() {
-                                    if square_type != SquareType::Square {
-                                        if hover_state.is_hover(row, col) {
-                                            color = "bg-amber-700";
-                                        }
+        div {
+            class: "board p-2 grid grid-cols-{cols}",
+            style: "{board_size} background-color: {board_color};",
+            onpointerdown: move |evt| touch_input.set(evt.pointer_type != "mouse"),
+            style { "{move_animations}" },
+            rows.clone().into_iter().map(|row| {
+                rsx! {
+                    div {
+                        class: "flex",
+                        (0..cols).map(|col| {
+                            let square_type = SquareType::at(row, col);
+
+                            let mut color = if col % 2 == 0 && row % 2 == 0 { square } else { groove };
+                            //if board.is_wall_probable_front_end(square_type, row,col) {
+                            //        color = "bg-gray-500";
+                            //}
+
+                            if let Some(hover_state) = hover_state.get() {
+                                if square_type != SquareType::Square {
+                                    if hover_state.is_hover(row, col) {
+                                        color = hover_wall;
                                     }
                                 }
-                                if is_part_of_wall(&board.read().board,square_type, row,col) {
-                                        color = "bg-amber-800";
+                            }
+                            if pending_wall_now.is_some_and(|pending| pending.is_hover(row, col)) {
+                                color = hover_wall;
+                            }
+                            if is_part_of_wall(board,square_type, row,col) {
+                                    color = wall;
+                            }
+                            let mut square_style = "";
+                            if square_type == SquareType::Square {
+                                if highlights.iter().any(|highlight| highlight.is_origin(row / 2, col / 2)) {
+                                    color = last_move_origin;
                                 }
-                                //if let Some((Move::Wall(dir, loc), (_, _))) = ai_suggest_move.get() {
-                                //        for wall in Walls::part_of_walls(square_type, row, col) {
-                                //            if wall ==  (*dir, *loc) {
-                                //                color = "bg-green-500";
-                                //            }
-                                //        }
-                                //}
-
-
-
-                                let current_hover_state = square_type.hover_state(row,col);
-                                rsx!{
-                                    div {
-                                        class: "border-0 border-grey-300 {square_type.width()} {square_type.height()} {color} flex justify-center items-center",
-                                        onmouseenter: move |_| {
-                                            hover_state.set(square_type.hover_state(row, col));
-                                        },
-                                        onclick: move |_| {
-                                            if players_turn {
-                                                if let Some(hover_state) = current_hover_state {
-                                                    match hover_state {
-                                                        HoverState::VerticalWall(r,c) => {
-                                                            let game_move = Move::Wall(WallDirection::Vertical,Position {row:(r/2) as i8,col:(c/2) as i8});
-                                                            board.with_mut(|board| board.game_move(game_move));
-                                                            worker.send_command(UserCommand::GameMove(game_move));
-                                                            ai_suggest_move.set(None);
-                                                        }
-                                                        HoverState::HorizontalWall(r,c) => {
-                                                            let game_move =Move::Wall(WallDirection::Horizontal,Position {row:(r/2) as i8,col:(c/2)as i8});
-                                                            board.with_mut(|board| board.game_move(game_move));
-                                                            worker.send_command(UserCommand::GameMove(game_move));
-                                                            // Only send this if valid move.
-                                                            ai_suggest_move.set(None);
-
-                                                        }
-                                                        HoverState::Pawn(_r,_c) => {
-                                                            ()
+                                if highlights.iter().any(|highlight| highlight.is_destination(row / 2, col / 2)) {
+                                    color = last_move_destination;
+                                }
+                            } else if highlights.iter().any(|highlight| highlight.is_wall_square(square_type, row, col)) {
+                                color = last_wall;
+                                if latest.is_some_and(|latest| latest.is_wall_square(square_type, row, col)) {
+                                    square_style = "animation: wall-drop 300ms ease-out;";
+                                }
+                            }
+                            //if let Some((Move::Wall(dir, loc), (_, _))) = ai_suggest_move.get() {
+                            //        for wall in Walls::part_of_walls(square_type, row, col) {
+                            //            if wall ==  (*dir, *loc) {
+                            //                color = "bg-green-500";
+                            //            }
+                            //        }
+                            //}
+
+
+
+                            let current_hover_state = square_type.hover_state(row,col);
+                            let size = square_type.size();
+                            rsx!{
+                                div {
+                                    class: "border-0 border-grey-300 flex justify-center items-center relative",
+                                    style: "{size} background-color: {color}; {square_style}",
+                                    onmouseenter: move |_| {
+                                        hover_state.set(square_type.hover_state(row, col));
+                                    },
+                                    onclick: move |_| {
+                                        if interactive {
+                                            if let Some(hover_state) = current_hover_state {
+                                                if let Some(game_move) = hover_state.wall_move() {
+                                                    if *touch_input.get() && pending_wall_now != Some(hover_state) {
+                                                        if let Move::Wall(direction, position) = game_move {
+                                                            if board.is_wall_allowed(direction, position) {
+                                                                pending_wall.set(Some((board.turn, hover_state)));
+                                                            }
                                                         }
+                                                        return;
                                                     }
+                                                    pending_wall.set(None);
+                                                    // Only send this if valid move.
+                                                    on_move.call(game_move);
                                                 }
                                             }
+                                        }
 
-                                        },
-                                        if square_type == SquareType::Square {
-                                            if let Some(pawn_index) = board.read().board.is_pawn(row/2,col/2) {
-                                                    let pawn_color = if pawn_index == 0 {
-                                                        "bg-slate-100"
-                                                    } else {
-                                                        "bg-slate-900"
-                                                    };
-                                                    rsx! {div {
-                                                        class: "{square_type.width()} {square_type.height()} {pawn_color} rounded-full",
-                                                    }
-                                                }
-                                            } else if let Some(pawn_move) = board.read().board.is_possible_next_pawn_location(row/2,col/2) {
-                                                if let Some(hover_square) = hover_square {
-                                                    if hover_square.is_hover(row, col) {
-                                                        let hover_color = if board.read().board.turn % 2 == 0 {
-                                                            "bg-slate-200"
-                                                        } else {
-                                                            "bg-slate-800"
-                                                        };
-                                                        rsx! {div {
-                                                            class: "{square_type.width()} {square_type.height()} {hover_color} rounded-full",
-                                                            onclick: move |_| { 
-                                                                if players_turn {
-                                                                    board.with_mut(|board| {
-                                                                            let game_move = Move::PawnMove(pawn_move.0, pawn_move.1);
-                                                                            let res = board.game_move(game_move);
-                                                                            worker.send_command(UserCommand::GameMove(game_move));
-                                                                            ai_suggest_move.set(None);
-                                                                            info!("Player move result: {:?}", res);
-                                                                    });
-                                                                }
-                                                            },
-                                                        }
-                                                    } 
-                                                } else {
-                                                    rsx! {div {}}
+                                    },
+                                    if square_type == SquareType::Square {
+                                        if let Some(pawn_index) = board.is_pawn(row/2,col/2) {
+                                                let pawn_color = pawns[pawn_index];
+                                                let outline = outline_style(pawn_outlines[pawn_index]);
+                                                let pawn_style = pawn_slide_style(latest, row / 2, col / 2, flipped);
+                                                rsx! {div {
+                                                    class: "rounded-full",
+                                                    style: "{size} background-color: {pawn_color}; {outline} {pawn_style}",
                                                 }
-                                            } else {
-                                                rsx! {div {}}
                                             }
+                                        } else if let Some(pawn_move) = board.is_possible_next_pawn_location(row/2,col/2) {
+                                            if let Some(hover_square) = hover_square {
+                                                if hover_square.is_hover(row, col) {
+                                                    let hover_color = pawn_previews[board.turn % 2];
+                                                    let outline = outline_style(pawn_outlines[board.turn % 2]);
+                                                    let title = jump_explanation(locale, pawn_move);
+                                                    rsx! {div {
+                                                        class: "rounded-full",
+                                                        style: "{size} background-color: {hover_color}; {outline}",
+                                                        title: "{title}",
+                                                        onclick: move |_| { 
+                                                            if interactive {
+                                                                on_move.call(Move::PawnMove(pawn_move.0, pawn_move.1));
+                                                            }
+                                                        },
+                                                    }
+                                                } 
                                             } else {
                                                 rsx! {div {}}
                                             }
+                                        } else {
+                                            rsx! {div {}}
+                                        }
+                                        } else {
+                                            rsx! {div {}}
                                         }
-                                        // Add your pawn and wall rendering logic here
+                                    }
+                                    // Add your pawn and wall rendering logic here
+                                    if let Some(hit_area) = square_type.hit_area() {
+                                        rsx! { div { style: "{hit_area}" } }
                                     }
                                 }
-                            })
-                        }
+                            }
+                        })
+                    }
+                }
+            })
+        }
+        if wall_pending {
+            rsx! { div { class: "p-2 text-center", locale.text("tap_wall_again") } }
+        }
+        }
+    })
+}
+
+#[derive(Props, PartialEq)]
+pub struct BookBadgeProps {
+    // Nothing is shown until the engine sent its first status.
+    #[props(!optional)]
+    status: Option<BookStatus>,
+}
+
+// Shows whether the AI is still playing from its preparation.
+fn BookBadge(cx: Scope<BookBadgeProps>) -> Element {
+    let locale = use_locale(cx);
+    let (color, text, title) = match cx.props.status? {
+        BookStatus::InBook { visits, win_rate_zero, mirrored } => (
+            "bg-green-500",
+            locale.format("book_in", &[("win_rate", &locale.percent(win_rate_zero, 0))]),
+            locale.format(
+                "book_in_title",
+                &[
+                    ("visits", &locale.count(visits.into())),
+                    ("win_rate", &locale.percent(win_rate_zero, 1)),
+                    ("mirrored", if mirrored { locale.text("book_mirrored") } else { "" }),
+                ],
+            ),
+        ),
+        BookStatus::InBookNoTree => (
+            "bg-amber-500",
+            locale.text("book_no_tree").to_string(),
+            locale.text("book_no_tree_title").to_string(),
+        ),
+        BookStatus::DownloadFailed => (
+            "bg-red-500",
+            locale.text("book_none").to_string(),
+            locale.text("book_none_title").to_string(),
+        ),
+        BookStatus::OutOfBook => (
+            "bg-gray-500",
+            locale.text("book_out").to_string(),
+            locale.text("book_out_title").to_string(),
+        ),
+    };
+    cx.render(rsx! {
+        div {
+            class: "{color} text-white font-bold py-2 px-4 rounded",
+            title: "{title}",
+            "{text}"
+        }
+    })
+}
+
+/// Whether white's pawn starts at the bottom of the board.
+pub fn white_at_bottom(flipped: bool) -> bool {
+    let start = Board::new();
+    (start.pawns[0].position.row > start.pawns[1].position.row) != flipped
+}
+
+#[derive(Props)]
+pub struct OrientationToggleProps<'a> {
+    flipped: &'a UseState<bool>,
+}
+
+// Says which side is at the bottom of the board, instead of only flipping it.
+pub fn OrientationToggle<'a>(cx: Scope<'a, OrientationToggleProps<'a>>) -> Element<'a> {
+    let flipped = cx.props.flipped;
+    let white_below = white_at_bottom(*flipped.get());
+    let locale = use_locale(cx);
+    let class = |active: bool| {
+        if active {
+            "bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        } else {
+            "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        }
+    };
+    cx.render(rsx! {
+        div { class: "flex space-x-1",
+            button {
+                class: class(white_below),
+                onclick: move |_| if !white_below { flipped.set(!*flipped.get()) },
+                locale.text("white_below")
+            },
+            button {
+                class: class(!white_below),
+                onclick: move |_| if white_below { flipped.set(!*flipped.get()) },
+                locale.text("black_below")
+            },
+        }
+    })
+}
+
+#[derive(Props, PartialEq)]
+pub struct QuoridorBoardProps {
+    /// The game or position the page was opened with.
+    #[props(!optional)]
+    link: Option<SharedLink>,
+}
+
+// The principal variation of the search in notation, it starts from `board`.
+fn line_notation(board: &Board, line: &[Move]) -> String {
+    let mut board = board.clone();
+    line.iter()
+        .map(|game_move| {
+            let notation = game_move.to_quoridor_strat_notation(&board);
+            board.game_move(*game_move);
+            notation
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn result_text(locale: Locale, result: GameResult) -> String {
+    match result {
+        GameResult::Goal { winner } => locale.format("result_goal", &[("player", locale.player(winner))]),
+        GameResult::Resigned { winner } => locale.format(
+            "result_resigned",
+            &[("player", locale.player(winner)), ("loser", locale.player(1 - winner))],
+        ),
+        GameResult::Draw(DrawReason::MoveLimit) => locale.text("result_move_limit").to_string(),
+        GameResult::Draw(DrawReason::Repetition) => locale.text("result_repetition").to_string(),
+    }
+}
+
+pub fn QuoridorBoard(cx: Scope<QuoridorBoardProps>) -> Element {
+    let ai_suggest_move: &UseState<Option<(Move, (usize, usize))>> = use_state(&cx, || None);
+    let board_flipped = use_state(&cx, || false);
+    // The number of moves of the earlier position that is shown instead of the game, picked in the graph.
+    let viewing: &UseState<Option<usize>> = use_state(cx, || None);
+
+    let worker_state = use_webworker(cx);
+    let WorkerState {
+        worker,
+        calc_update,
+        board,
+        ai_player,
+        book_status,
+        analysis,
+    } = worker_state;
+    let locale = use_locale(cx);
+    let progress = match &calc_update.get() {
+        CalculateUpdate::Progress(progress) => *progress,
+        _ => 0.0,
+    };
+    let progress = locale.percent(progress, 0);
+
+    let encoding: &UseState<String> = use_state(&cx, || "".to_string());
+    // Both sides are played by the user and the engine keeps searching the position, instead of playing one side.
+    let analysis_mode = use_state(cx, || cx.props.link.is_some());
+
+    use_effect(cx, (&cx.props.link,), |(link,)| {
+        if let Some(link) = link {
+            match link.board() {
+                Ok(history) => {
+                    // The engine follows the same moves, so its evaluations line up with the moves of the game.
+                    worker.send_command(UserCommand::DecodeBoard(history.start.encode()));
+                    for game_move in &history.moves {
+                        worker.send_command(UserCommand::GameMove(*game_move));
                     }
-                })
+                    worker.send_command(UserCommand::Go(SearchLimit::Infinite));
+                    board.set(history);
+                    analysis_mode.set(true);
+                }
+                Err(err) => log::warn!("Could not open the link: {}", err),
+            }
+        }
+        async {}
+    });
+
+
+
+    let current_ai_player = *ai_player.get();
+    let players_turn = current_ai_player.is_some() && board.read().board.turn % 2 != current_ai_player.unwrap();
+    let result = board.read().result;
+    let shown_board = match *viewing.get() {
+        Some(position) => board.read().board_at(position),
+        None => board.read().board.clone(),
+    };
+    let mut evaluations = board.read().evaluations.clone();
+    evaluations.resize(board.read().moves.len() + 1, None);
+    // Where the analysis found a better move than the one played, the diagram can point it out. Otherwise the current
+    // position gets the first move of the line of the engine, as long as that line is still of this position.
+    let analyzed_suggestion = match analysis.get() {
+        Some(AnalysisState::Done(game_analysis)) => game_analysis
+            .moves
+            .get(viewing.get().unwrap_or(board.read().moves.len()))
+            .and_then(|analyzed| analyzed.best_move.as_ref())
+            .and_then(|best_move| parse_move(&shown_board, best_move)),
+        _ => None,
+    };
+    let engine_suggestion = match (calc_update.get(), viewing.get()) {
+        (CalculateUpdate::Info(info), None) => info
+            .principal_variation
+            .first()
+            .copied()
+            .filter(|game_move| legal_moves(&shown_board).contains(game_move)),
+        _ => None,
+    };
+    let suggestion = analyzed_suggestion.or(engine_suggestion);
+    let shown_encoding = shown_board.encode();
+    let engine_line = match calc_update.get() {
+        CalculateUpdate::Info(info) if *analysis_mode.get() => {
+            let board = &board.read().board;
+            Some(locale.format(
+                "engine_line",
+                &[
+                    ("player", locale.player(board.turn % 2)),
+                    ("win_rate", &locale.percent(info.win_rate, 0)),
+                    ("visits", &locale.count(info.visits.into())),
+                    ("line", &line_notation(board, &info.principal_variation)),
+                ],
+            ))
+        }
+        _ => None,
+    }
+    .unwrap_or_else(|| locale.text("engine_searching").to_string());
+    let share = SharedLink::new(&board.read());
+    let variety = if use_settings(cx).opening_variety { Variety::PRACTICE } else { Variety::NONE };
+    cx.render(rsx! {
+        div { class: "page-columns flex flex-col justify-center items-center gap-4",
+        div { class: "flex flex-col items-center",
+            BoardGrid {
+                board: shown_board,
+                highlights: board.read().last_moves(viewing.get().unwrap_or(board.read().moves.len())),
+                flipped: *board_flipped.get(),
+                // A finished game can still be played on as an analysis board.
+                interactive: ((players_turn && result.is_none()) || *analysis_mode.get()) && viewing.get().is_none(),
+                on_move: move |game_move: Move| {
+                    board.with_mut(|board| board.game_move(game_move));
+                    worker.send_command(UserCommand::GameMove(game_move));
+                    if *analysis_mode.get() {
+                        // The search of the position before is of no use anymore.
+                        calc_update.set(CalculateUpdate::Progress(0.0));
+                        worker.send_command(UserCommand::Go(SearchLimit::Infinite));
+                    }
+                    ai_suggest_move.set(None);
+                    info!("Player move: {:?}", game_move);
+                },
+            },
+            WinRateGraph {
+                evaluations: evaluations,
+                selected: *viewing.get(),
+                on_select: move |position: usize| {
+                    if position == board.read().moves.len() {
+                        viewing.set(None);
+                    } else {
+                        viewing.set(Some(position));
+                    }
+                },
             },
+            if let Some(position) = viewing.get() {
+                let back = locale.format("back_to_game", &[("position", &locale.count(*position as u64))]);
+                rsx! {
+                    button {
+                        class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                        onclick: move |_| viewing.set(None),
+                        "{back}"
+                    }
+                }
+            }
             cx.render(rsx! {
                 div { class: "flex flex-wrap justify-center items-center space-x-2 p-4",
                     div { class: "flex flex-col items-center p-2",
-                        div { class: "text-3xl font-bold", "TURN" },
+                        div { class: "text-3xl font-bold", locale.text("turn") },
                         div { class: "text-4xl font-bold", "{board.read().board.turn + 1}" }
                     },
                     div { class: "flex flex-col items-center p-2",
-                        div { class: "text-3xl font-bold", "WHITE" },
+                        div { class: "text-3xl font-bold", locale.text("white") },
                         // Assuming pawn 0's walls are correctly retrieved with a direct method or similar access
                         div { class: "text-4xl font-bold", "{board.read().board.pawns[0].number_of_walls_left}" }
                     },
                     div { class: "flex flex-col items-center p-2",
-                        div { class: "text-3xl font-bold", "BLACK" },
+                        div { class: "text-3xl font-bold", locale.text("black") },
                         // Corrected to use the specific field for pawn 1 as indicated
                         div { class: "text-4xl font-bold", "{board.read().board.pawns[1].number_of_walls_left}" }
                     }
                     div { class: "w-full p-4 flex flex-col items-center",
-                        div { class: "text-2xl font-semibold", "Moves History: " },
+                        div { class: "text-2xl font-semibold", locale.text("moves_history") },
                         div { class: "w-full max-h-[200px] overflow-auto p-2",
                             div { class: "text-xl", "{board.read().historic_moves()}" }
                         }
                     }
+                    match analysis.get() {
+                        Some(AnalysisState::Running(progress)) => {
+                            let analyzing = locale.format("analyzing_game", &[("progress", &locale.percent(*progress, 0))]);
+                            rsx! { div { class: "text-xl p-4", "{analyzing}" } }
+                        }
+                        Some(AnalysisState::Done(game_analysis)) => rsx! {
+                            AnalysisReport { analysis: game_analysis.clone() }
+                        },
+                        None => rsx! { div {} },
+                    }
                 }
             }),
         },
-        div { class: "flex flex-col space-y-2",
-            button {
-                class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
-                onclick: move |_| {
-                    board_flipped.with_mut(|board| {
-                        *board = !*board;
-                    });
-                },
-                "FLIP BOARD"
-            },
+        div { class: "game-panel flex flex-col space-y-2 w-full p-2",
+            if let Some(result) = result {
+                let text = result_text(locale, result);
+                rsx! { div { class: "bg-amber-400 font-bold py-2 px-4 rounded", "{text}" } }
+            }
+            OrientationToggle { flipped: board_flipped },
+            if *analysis_mode.get() {
+                rsx! {
+                    div { class: "text-xl font-semibold", locale.text("analysis_board") },
+                    div { class: "text-sm max-w-xs", "{engine_line}" },
+                }
+            }
+            ShareLink { link: share },
             div {
                 class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
                 // Assuming 'progress' is a state or prop you're tracking
-                "{progress}%"
+                "{progress}"
+            }
+            BookBadge { status: *book_status.get() },
+            DiagramExport {
+                encoding: shown_encoding,
+                flipped: *board_flipped.get(),
+                suggestion: suggestion,
+            },
+            if !matches!(analysis.get(), Some(AnalysisState::Running(_))) && !board.read().moves.is_empty() {
+                rsx! {
+                    button {
+                        class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                        onclick: move |_| worker_state.analyze(),
+                        locale.text("analyze_game")
+                    }
+                }
+            }
+            // Only on the turn of the user, so the AI can't answer a move that is no longer wanted.
+            if players_turn && result.is_none() && !*analysis_mode.get() {
+                rsx! {
+                    button {
+                        class: "bg-red-500 hover:bg-red-700 text-white font-bold py-2 px-4 rounded",
+                        onclick: move |_| {
+                            if let Some(ai) = *ai_player.get() {
+                                board.with_mut(|board| board.resign(1 - ai));
+                                worker.send_command(UserCommand::Resign(1 - ai));
+                            }
+                        },
+                        locale.text("resign")
+                    }
+                }
             }
             if ai_player.get().is_none() {
                 rsx!{
@@ -364,9 +704,14 @@ pub fn QuoridorBoard(cx: Scope) -> Element {
                         ai_player.with_mut(|ai_pawn| {
                             *ai_pawn = Some(0);
                         });
+                        if *analysis_mode.get() {
+                            analysis_mode.set(false);
+                            worker.send_command(UserCommand::Stop);
+                        }
+                        worker.send_command(UserCommand::SetVariety(variety));
                         worker.send_command(UserCommand::SetAIPlayer(0));
                     },
-                    "PLAY BLACK"
+                    locale.text("play_black")
                 },
                 button {
                     class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
@@ -374,10 +719,27 @@ pub fn QuoridorBoard(cx: Scope) -> Element {
                         ai_player.with_mut(|ai_pawn| {
                             *ai_pawn = Some(1);
                         });
+                        if *analysis_mode.get() {
+                            analysis_mode.set(false);
+                            worker.send_command(UserCommand::Stop);
+                        }
+                        worker.send_command(UserCommand::SetVariety(variety));
                         worker.send_command(UserCommand::SetAIPlayer(1));
                     },
-                    "PLAY WHITE"
+                    locale.text("play_white")
                 },
+                if !*analysis_mode.get() {
+                    rsx! {
+                        button {
+                            class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                            onclick: move |_| {
+                                analysis_mode.set(true);
+                                worker.send_command(UserCommand::Go(SearchLimit::Infinite));
+                            },
+                            locale.text("analysis_board")
+                        }
+                    }
+                }
                 }
             }  else {
                 rsx! {div{}}
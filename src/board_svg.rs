@@ -0,0 +1,279 @@
+use dioxus::prelude::*;
+
+use quoridor::*;
+
+use quoridor_frontend::grid::*;
+
+use crate::board_fr::{jump_explanation, BoardGridProps, BOARD_SIZE, MOVE_ANIMATIONS};
+use crate::settings::{use_locale, use_settings};
+
+// In the units of the view box, a square is 4 wide and a groove 1, so the 9 squares and 8 grooves are 44 wide.
+const SQUARE: f32 = 4.0;
+const GROOVE: f32 = 1.0;
+const PITCH: f32 = SQUARE + GROOVE;
+const SIZE: f32 = DIMENSION as f32 * PITCH - GROOVE;
+const PAWN_RADIUS: f32 = 1.6;
+// The grooves are widened by this on both sides to catch taps and clicks, so they are easier to hit on a touch screen.
+const HIT_MARGIN: f32 = 0.75;
+
+// What the pointer is on, in board coordinates.
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    Square(usize, usize),
+    Wall(WallDirection, Position),
+}
+
+// The top left corner of a square of the board.
+fn square_origin(row: usize, col: usize, flipped: bool) -> (f32, f32) {
+    let shown_row = if flipped { DIMENSION - 1 - row } else { row };
+    (col as f32 * PITCH, shown_row as f32 * PITCH)
+}
+
+// The rectangle of a wall, `x`, `y`, width and height. A wall starts at its position and covers the groove next to that
+// square and the square after it.
+fn wall_rect(direction: WallDirection, position: Position, flipped: bool) -> (f32, f32, f32, f32) {
+    let col = position.col as f32;
+    // The upper one of the two rows the wall touches, as it's shown.
+    let top_row = if flipped {
+        (DIMENSION - 2) as f32 - position.row as f32
+    } else {
+        position.row as f32
+    };
+    match direction {
+        WallDirection::Horizontal => (col * PITCH, top_row * PITCH + SQUARE, 2.0 * SQUARE + GROOVE, GROOVE),
+        WallDirection::Vertical => (col * PITCH + SQUARE, top_row * PITCH, GROOVE, 2.0 * SQUARE + GROOVE),
+    }
+}
+
+// Where the pointer selects a wall: the first square long piece of its groove, made thicker by `HIT_MARGIN`. This is the
+// same piece of groove that selects the wall on the div board.
+fn wall_hit_rect(direction: WallDirection, position: Position, flipped: bool) -> (f32, f32, f32, f32) {
+    let (x, y, _, _) = wall_rect(direction, position, flipped);
+    match direction {
+        WallDirection::Horizontal => {
+            // Flipped, the first piece is still the one at the column of the position, the rows are what turns around.
+            (x, y - HIT_MARGIN, SQUARE, GROOVE + 2.0 * HIT_MARGIN)
+        }
+        WallDirection::Vertical => {
+            let y = if flipped { y + PITCH } else { y };
+            (x - HIT_MARGIN, y, GROOVE + 2.0 * HIT_MARGIN, SQUARE)
+        }
+    }
+}
+
+fn all_walls() -> impl Iterator<Item = (WallDirection, Position)> {
+    [WallDirection::Horizontal, WallDirection::Vertical]
+        .into_iter()
+        .flat_map(|direction| {
+            (0..(DIMENSION - 1) as i8).flat_map(move |row| {
+                (0..(DIMENSION - 1) as i8).map(move |col| (direction, Position { row, col }))
+            })
+        })
+}
+
+// The board as a single SVG. Squares, walls and pawns are drawn from the `Board` directly, and the grooves have
+// invisible, wider shapes on top to pick the wall the pointer is on.
+pub fn BoardSvg<'a>(cx: Scope<'a, BoardGridProps<'a>>) -> Element<'a> {
+    let hover: &UseState<Option<Target>> = use_state(cx, || None);
+    // On a touch screen the first tap only shows the move and a second tap makes it, like on the div board.
+    let touch_input = use_state(cx, || false);
+    let pending: &UseState<Option<(usize, Target)>> = use_state(cx, || None);
+
+    let board = &cx.props.board;
+    let flipped = cx.props.flipped;
+    let interactive = cx.props.interactive;
+    let on_move = &cx.props.on_move;
+    let highlights = &cx.props.highlights;
+    let settings = use_settings(cx);
+    let locale = use_locale(cx);
+    let theme = settings.theme.theme();
+    let latest = highlights.last().filter(|_| settings.animate_moves);
+    let pending_now = pending
+        .get()
+        .filter(|(turn, _)| *turn == board.turn)
+        .map(|(_, target)| target);
+    let shown = pending_now.or(*hover.get());
+
+    let select = move |target: Target, game_move: Move| {
+        if !interactive {
+            return;
+        }
+        if *touch_input.get() && pending_now != Some(target) {
+            pending.set(Some((board.turn, target)));
+            return;
+        }
+        pending.set(None);
+        on_move.call(game_move);
+    };
+
+    let board_color = theme.board;
+    let placed_walls: Vec<(WallDirection, Position)> = all_walls()
+        .filter(|(direction, position)| board.walls.is_allowed(*direction, *position))
+        .collect();
+    let explanation = match shown {
+        Some(Target::Square(row, col)) if interactive => board
+            .is_possible_next_pawn_location(row, col)
+            .map(|pawn_move| jump_explanation(locale, pawn_move))
+            .unwrap_or(""),
+        _ => "",
+    };
+    let view_box = format!("{} {} {} {}", -GROOVE, -GROOVE, SIZE + 2.0 * GROOVE, SIZE + 2.0 * GROOVE);
+    let board_size = BOARD_SIZE;
+    let move_animations = MOVE_ANIMATIONS;
+
+    cx.render(rsx! {
+        div { class: "flex flex-col items-center", style: "{board_size}",
+            style { "{move_animations}" },
+            svg {
+                class: "board p-2",
+                style: "width: calc(var(--square) * 11.5); height: calc(var(--square) * 11.5); background-color: {board_color};",
+                view_box: "{view_box}",
+                onpointerdown: move |evt| touch_input.set(evt.pointer_type != "mouse"),
+                onmouseleave: move |_| hover.set(None),
+                rect {
+                    fill: "{theme.groove}",
+                    x: "{-GROOVE}",
+                    y: "{-GROOVE}",
+                    width: "{SIZE + 2.0 * GROOVE}",
+                    height: "{SIZE + 2.0 * GROOVE}",
+                },
+                (0..DIMENSION).flat_map(|row| (0..DIMENSION).map(move |col| (row, col))).map(|(row, col)| {
+                    let (x, y) = square_origin(row, col, flipped);
+                    let mut fill = theme.square;
+                    if highlights.iter().any(|highlight| highlight.is_origin(row, col)) {
+                        fill = theme.last_move_origin;
+                    }
+                    if highlights.iter().any(|highlight| highlight.is_destination(row, col)) {
+                        fill = theme.last_move_destination;
+                    }
+                    let pawn_move = board.is_possible_next_pawn_location(row, col);
+                    rsx! {
+                        rect {
+                            key: "square-{row}-{col}",
+                            fill: "{fill}",
+                            x: "{x}",
+                            y: "{y}",
+                            width: "{SQUARE}",
+                            height: "{SQUARE}",
+                            onmouseenter: move |_| hover.set(Some(Target::Square(row, col))),
+                            onclick: move |_| {
+                                if let Some(pawn_move) = pawn_move {
+                                    select(Target::Square(row, col), Move::PawnMove(pawn_move.0, pawn_move.1));
+                                }
+                            },
+                        }
+                    }
+                }),
+                placed_walls.iter().map(|(direction, position)| {
+                    let (x, y, width, height) = wall_rect(*direction, *position, flipped);
+                    let last = highlights.contains(&MoveHighlight::Wall(*direction, *position));
+                    let fill = if last { theme.last_wall } else { theme.wall };
+                    let animation = if latest == Some(&MoveHighlight::Wall(*direction, *position)) {
+                        "animation: wall-drop 300ms ease-out; transform-box: fill-box; transform-origin: center;"
+                    } else {
+                        ""
+                    };
+                    rsx! {
+                        rect {
+                            key: "wall-{direction:?}-{position.row}-{position.col}",
+                            fill: "{fill}",
+                            style: "pointer-events: none; {animation}",
+                            x: "{x}",
+                            y: "{y}",
+                            width: "{width}",
+                            height: "{height}",
+                        }
+                    }
+                }),
+                (0..2).map(|player| {
+                    let position = board.pawns[player].position;
+                    let (row, col) = (position.row as usize, position.col as usize);
+                    let (x, y) = square_origin(row, col, flipped);
+                    let animation = match latest {
+                        Some(latest @ MoveHighlight::Pawn { from, to }) if latest.is_destination(row, col) => {
+                            let flip = if flipped { -1.0 } else { 1.0 };
+                            format!(
+                                "--dx: {}px; --dy: {}px; animation: pawn-slide 300ms ease-out;",
+                                (from.col - to.col) as f32 * PITCH,
+                                (from.row - to.row) as f32 * PITCH * flip,
+                            )
+                        }
+                        _ => String::new(),
+                    };
+                    let fill = theme.pawns[player];
+                    let stroke = theme.pawn_outlines[player].unwrap_or("none");
+                    rsx! {
+                        circle {
+                            key: "pawn-{player}",
+                            fill: "{fill}",
+                            stroke: "{stroke}",
+                            style: "pointer-events: none; {animation}",
+                            cx: "{x + SQUARE / 2.0}",
+                            cy: "{y + SQUARE / 2.0}",
+                            r: "{PAWN_RADIUS}",
+                            stroke_width: "0.3",
+                        }
+                    }
+                }),
+                match shown {
+                    Some(Target::Square(row, col)) if interactive && board.is_possible_next_pawn_location(row, col).is_some() => {
+                        let (x, y) = square_origin(row, col, flipped);
+                        let fill = theme.pawn_previews[board.turn % 2];
+                        let stroke = theme.pawn_outlines[board.turn % 2].unwrap_or("none");
+                        rsx! {
+                            circle {
+                                fill: "{fill}",
+                                stroke: "{stroke}",
+                                style: "pointer-events: none;",
+                                cx: "{x + SQUARE / 2.0}",
+                                cy: "{y + SQUARE / 2.0}",
+                                r: "{PAWN_RADIUS}",
+                                stroke_width: "0.3",
+                            }
+                        }
+                    }
+                    Some(Target::Wall(direction, position)) if interactive => {
+                        let (x, y, width, height) = wall_rect(direction, position, flipped);
+                        let fill = theme.hover_wall;
+                        rsx! {
+                            rect {
+                                fill: "{fill}",
+                                style: "pointer-events: none;",
+                                x: "{x}",
+                                y: "{y}",
+                                width: "{width}",
+                                height: "{height}",
+                            }
+                        }
+                    }
+                    _ => rsx! { g {} },
+                },
+                all_walls().map(|(direction, position)| {
+                    let (x, y, width, height) = wall_hit_rect(direction, position, flipped);
+                    rsx! {
+                        rect {
+                            key: "hit-{direction:?}-{position.row}-{position.col}",
+                            fill: "transparent",
+                            x: "{x}",
+                            y: "{y}",
+                            width: "{width}",
+                            height: "{height}",
+                            onmouseenter: move |_| hover.set(Some(Target::Wall(direction, position))),
+                            onclick: move |_| {
+                                if board.is_wall_allowed(direction, position) {
+                                    select(Target::Wall(direction, position), Move::Wall(direction, position));
+                                }
+                            },
+                        }
+                    }
+                })
+            },
+            if !explanation.is_empty() {
+                rsx! { div { class: "p-2 text-center", "{explanation}" } }
+            }
+            if interactive && matches!(pending_now, Some(Target::Wall(..))) {
+                rsx! { div { class: "p-2 text-center", locale.text("tap_wall_again") } }
+            }
+        }
+    })
+}
@@ -0,0 +1,82 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use quoridor::{Board, MonteCarloTree};
+
+use crate::engine::BookError;
+
+/// The name of the manifest next to `to_precalc.json`.
+pub const MANIFEST_FILE: &str = "book_manifest.json";
+/// The format `MonteCarloTree::serialize` writes. Bump it when the quoridor library changes that format, trees of
+/// another version are not used anymore.
+pub const TREE_FORMAT_VERSION: u32 = 1;
+
+/// What the `.mc_node` bytes of one book tree should look like.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TreeEntry {
+    pub version: u32,
+    pub length: usize,
+    pub crc32: u32,
+}
+
+impl TreeEntry {
+    pub fn new(bytes: &[u8]) -> TreeEntry {
+        TreeEntry {
+            version: TREE_FORMAT_VERSION,
+            length: bytes.len(),
+            crc32: crc32fast::hash(bytes),
+        }
+    }
+}
+
+/// The checksums of all trees of the book, by the encoding of their position. A tree is only handed to
+/// `MonteCarloTree::deserialize` if it matches, so a truncated download or an error page can't end up in the search.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct BookManifest {
+    pub trees: BTreeMap<String, TreeEntry>,
+}
+
+impl BookManifest {
+    /// Whether the book has a tree of the position with `encoding`. Book positions without one only have a score.
+    pub fn has_tree(&self, encoding: &str) -> bool {
+        self.trees.contains_key(encoding)
+    }
+
+    pub fn verify(&self, encoding: &str, bytes: &[u8]) -> Result<(), BookError> {
+        let expected = self
+            .trees
+            .get(encoding)
+            .ok_or_else(|| format!("{} is not in the manifest", encoding))?;
+        if expected.version != TREE_FORMAT_VERSION {
+            return Err(format!(
+                "{} has tree format {}, expected {}",
+                encoding, expected.version, TREE_FORMAT_VERSION
+            ))?;
+        }
+        let found = TreeEntry::new(bytes);
+        if found.length != expected.length {
+            return Err(format!(
+                "{} is {} bytes, expected {}",
+                encoding, found.length, expected.length
+            ))?;
+        }
+        if found.crc32 != expected.crc32 {
+            return Err(format!("{} has the wrong checksum", encoding))?;
+        }
+        Ok(())
+    }
+}
+
+/// The tree of `board` from `bytes`, if they match the manifest. Without a manifest nothing can be checked, so then
+/// no tree is used at all.
+pub fn verified_tree(
+    manifest: Option<&BookManifest>,
+    board: &Board,
+    bytes: &[u8],
+) -> Result<MonteCarloTree, BookError> {
+    manifest
+        .ok_or("the book has no manifest to check its trees")?
+        .verify(&board.encode(), bytes)?;
+    Ok(MonteCarloTree::deserialize(bytes))
+}
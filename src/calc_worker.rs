@@ -1,161 +1,148 @@
-use std::collections::VecDeque;
-use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::ops::{Deref, Range};
+use std::rc::Rc;
 
-use dioxus::prelude::*;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::future::{select, Either};
+use futures::StreamExt;
 use gloo::timers::future::TimeoutFuture;
 use js_sys::Uint8Array;
-use log::info;
-use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, Worker, WorkerOptions, WorkerType};
 
-use quoridor::{AIControlledBoard, Board, MirrorMoveType, MonteCarloTree, Move, PreCalc};
+use quoridor::{Board, MonteCarloTree, Move, PreCalc};
+use quoridor_frontend::engine::{
+    BookError, BookFetcher, CommandSource, EngineSession, SeenTableNew, UpdateSink,
+};
+use quoridor_frontend::engine::{CalculateUpdate, UserCommand};
+use quoridor_frontend::book_manifest::{verified_tree, BookManifest, MANIFEST_FILE};
+use quoridor_frontend::packed_book::{unpack, PackedIndex, HEADER_LEN, PACKED_BOOK_FILE};
+
+use crate::quoridor_worker::WorkerBackend;
 
 //const BASE_URL: &str = "https://janpel.github.io/quoridor_frontend/";
 const BASE_URL: &str = "http://localhost:8080/";
 //const BASE_URL: &str = "https://storage.googleapis.com/quoridor_openingbook/";
 
-#[derive(Clone, Copy)]
-pub struct QuoridorWorker<'a> {
-    worker: &'a Worker,
-}
+/// The main thread side of a web worker. The worker is terminated when this is dropped, with the page that used it,
+/// otherwise it would keep searching with nobody listening.
+pub struct WebWorker(Worker);
 
-pub struct BoardWithHistory {
-    pub board: Board,
-    pub historic_moves: Vec<String>,
+impl Drop for WebWorker {
+    fn drop(&mut self) {
+        log::info!("Terminating webworker");
+        self.0.terminate();
+    }
 }
 
-impl BoardWithHistory {
-    fn new() -> Self {
-        BoardWithHistory {
-            board: Board::new(),
-            historic_moves: vec![],
-        }
-    }
+// Updates from the worker are passed on to `updates`.
+pub fn new_web_worker(updates: UnboundedSender<CalculateUpdate>) -> WebWorker {
+    let worker = Worker::new_with_options("worker.js", &worker_options()).unwrap();
 
-    pub fn game_move(&mut self, game_move: Move) {
-        let quoridor_strats_move = game_move.to_quoridor_strat_notation(&self.board);
-        self.historic_moves.push(quoridor_strats_move);
-        self.board.game_move(game_move);
-    }
+    let f: Closure<dyn Fn(MessageEvent) -> ()> = Closure::new(move |event: MessageEvent| {
+        let data = event.data();
+        let uint8_array: Uint8Array = data.into();
+        // Create a Vec<u8> with the same length as the Uint8Array
+        let mut bytes = vec![0; uint8_array.length() as usize];
+        uint8_array.copy_to(&mut bytes);
+        // Copy the contents of the Uint8Array into the Vec<u8>
+        let calculate_update: CalculateUpdate = bincode::deserialize(&bytes).unwrap();
+        let _ = updates.unbounded_send(calculate_update);
+    });
 
-    pub fn historic_moves(&self) -> String {
-        self.historic_moves.join(";")
-    }
+    let val = f.into_js_value();
+    let f = js_sys::Function::unchecked_from_js(val);
+    worker.set_onmessage(Some(&f));
+    WebWorker(worker)
 }
 
-impl<'a> QuoridorWorker<'a> {
-    pub fn send_command(&self, command: UserCommand) {
-        let encoded = bincode::serialize(&command).unwrap();
+impl WorkerBackend for WebWorker {
+    // The command goes with the time it was sent, see `CommandChannel`.
+    fn send_command(&self, command: UserCommand) {
+        let encoded = bincode::serialize(&(js_sys::Date::now(), command)).unwrap();
         let uint8_array = js_sys::Uint8Array::new_with_length(encoded.len() as u32);
         uint8_array.copy_from(&encoded);
-        log::info!("Sending command to worker: {:?}", command);
-        self.worker.post_message(&JsValue::from(uint8_array));
+        self.0.post_message(&JsValue::from(uint8_array));
     }
 }
 
-pub fn use_webworker(
-    cx: &ScopeState,
-) -> (
-    QuoridorWorker,
-    &UseState<CalculateUpdate>,
-    &UseRef<BoardWithHistory>,
-    &UseState<Option<usize>>,
-) {
-    let latest_update = use_state(cx, || CalculateUpdate::Progress(0.0));
-    let board = use_ref(cx, || BoardWithHistory::new());
-    let ai_player: &UseState<Option<usize>> = use_state(cx, || None);
-
-    let worker = cx.use_hook(|| {
-        let worker = Worker::new_with_options("worker.js", &worker_options()).unwrap();
-
-        let latest_update = latest_update.clone();
-        let board = board.clone();
-        let f: Closure<dyn Fn(MessageEvent) -> ()> = Closure::new(move |event: MessageEvent| {
-            let data = event.data();
-            let uint8_array: Uint8Array = data.into();
-            // Create a Vec<u8> with the same length as the Uint8Array
-            let mut bytes = vec![0; uint8_array.length() as usize];
-            uint8_array.copy_to(&mut bytes);
-            // Copy the contents of the Uint8Array into the Vec<u8>
-            let calculate_update: CalculateUpdate = bincode::deserialize(&bytes).unwrap();
-
-            match calculate_update {
-                CalculateUpdate::Finish(game_move) => {
-                    //log::info!("AI finish move suggested : {:?}", game_move);
-                    board.with_mut(|board| {
-                        let res = board.game_move(game_move);
-                        info!("Taking AI {:?} MOVE AUTOMATICALLY: {:?}", game_move, res);
-                    });
-                }
-                CalculateUpdate::Progress(f) => {
-                    latest_update.set(CalculateUpdate::Progress(f));
-                }
-            }
-            latest_update.set(calculate_update);
-        });
-
-        let val = f.into_js_value();
-        let f = js_sys::Function::unchecked_from_js(val);
-        worker.set_onmessage(Some(&f));
-        worker
-    });
-
-    (QuoridorWorker { worker }, latest_update, board, ai_player)
-}
-
 fn worker_options() -> WorkerOptions {
     let mut options = WorkerOptions::new();
     options.type_(WorkerType::Module);
     options
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub enum UserCommand {
-    DecodeBoard,
-    GameMove(Move),
-    SetAIPlayer(usize),
+// How many latencies go into one summary in the log.
+const LATENCY_REPORT_COMMANDS: usize = 20;
+
+// Commands are stamped on the main thread with the time they were sent, so we can log how long it took until the
+// search loop picked them up. That includes the time a command waits in the message queue of the worker while a search
+// batch is running, which a stamp taken in `onmessage` would leave out. Both threads read the same wall clock.
+struct CommandChannel {
+    user_commands: UnboundedReceiver<(UserCommand, f64)>,
+    // Of the commands since the last summary, in milliseconds.
+    latencies: Vec<f64>,
+}
+
+impl CommandSource for CommandChannel {
+    async fn recv(&mut self) -> Option<UserCommand> {
+        let (command, sent_at) = self.user_commands.next().await?;
+        self.record_latency(&command, sent_at);
+        Some(command)
+    }
+
+    // Yields to the event loop, so the `onmessage` handler can run.
+    async fn recv_or_yield(&mut self) -> Option<UserCommand> {
+        match select(self.user_commands.next(), TimeoutFuture::new(0)).await {
+            Either::Left((Some((command, sent_at)), _)) => {
+                self.record_latency(&command, sent_at);
+                Some(command)
+            }
+            _ => None,
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
-pub enum CalculateUpdate {
-    Finish(Move),
-    Progress(f32),
+impl CommandChannel {
+    // Logs every latency, and every `LATENCY_REPORT_COMMANDS` commands the median and the worst of them, which is
+    // what the move latency is reported with.
+    fn record_latency(&mut self, command: &UserCommand, sent_at: f64) {
+        let latency = js_sys::Date::now() - sent_at;
+        log::info!("Picked up {:?} {:.1} ms after it was sent", command, latency);
+        self.latencies.push(latency);
+        if self.latencies.len() < LATENCY_REPORT_COMMANDS {
+            return;
+        }
+        self.latencies.sort_by(f64::total_cmp);
+        log::info!(
+            "Command latency over {} commands: median {:.1} ms, max {:.1} ms",
+            self.latencies.len(),
+            self.latencies[self.latencies.len() / 2],
+            self.latencies[self.latencies.len() - 1]
+        );
+        self.latencies.clear();
+    }
 }
 
 struct WorkerUpdates {
     scope: DedicatedWorkerGlobalScope,
 }
 
-impl WorkerUpdates {
-    pub fn send_update(&self, update: CalculateUpdate) {
+impl UpdateSink for WorkerUpdates {
+    fn send_update(&self, update: CalculateUpdate) {
         let encoded = bincode::serialize(&update).unwrap();
         let uint8_array = js_sys::Uint8Array::new_with_length(encoded.len() as u32);
         uint8_array.copy_from(&encoded);
         self.scope.post_message(&JsValue::from(uint8_array));
     }
-}
 
-#[derive(Clone)]
-struct CommandChannel {
-    user_commands: Arc<Mutex<VecDeque<UserCommand>>>,
-}
-
-impl CommandChannel {
-    pub fn recv_next(&self) -> Option<UserCommand> {
-        self.user_commands.lock().unwrap().pop_front()
+    fn report_seen_table(&self, seen_table: SeenTableNew, historic_moves: &[Move]) {
+        wasm_bindgen_futures::spawn_local(add_table_ignore_error(
+            seen_table,
+            historic_moves.to_vec(),
+        ));
     }
 }
 
-#[derive(Deserialize, Serialize)]
-struct SeenTableNew {
-    tabel: String,
-    win_rate: f64,
-    visits: u32,
-    ai_player: bool,
-}
-
 pub fn quoridor_strats_moves(historic_moves: &Vec<Move>) -> Vec<String> {
     let mut board = Board::new();
     let mut quoridor_strats_moves = vec![];
@@ -168,23 +155,13 @@ pub fn quoridor_strats_moves(historic_moves: &Vec<Move>) -> Vec<String> {
 }
 
 async fn add_table(
-    board: &Board,
-    win_rate: f64,
-    visits: u32,
-    ai_player_zero: bool,
+    payload: &SeenTableNew,
     historic_moves: Vec<Move>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let client = reqwest::Client::new();
     let url = "https://quoridorwebsite.shuttleapp.rs/seen_tables"; // Adjust the URL path as necessary
 
-    let payload = SeenTableNew {
-        tabel: board.encode(),
-        win_rate,
-        visits,
-        ai_player: ai_player_zero,
-    };
-
-    let response = client.post(url).json(&payload).send().await?;
+    let response = client.post(url).json(payload).send().await?;
     let status = response.status();
     if status.is_success() {
         Ok(())
@@ -198,44 +175,13 @@ async fn add_table(
     }
 }
 
-async fn add_table_ignore_error(
-    board: Board,
-    win_rate: f64,
-    visits: u32,
-    ai_player_zero: bool,
-    historic_moves: Vec<Move>,
-) {
-    if let Err(err) = add_table(&board, win_rate, visits, ai_player_zero, historic_moves).await {
+async fn add_table_ignore_error(payload: SeenTableNew, historic_moves: Vec<Move>) {
+    if let Err(err) = add_table(&payload, historic_moves).await {
         log::warn!("{}", err);
     }
 }
 
-async fn store_table_if_unknown_and_ai_loses(
-    ai_controlled_board: &AIControlledBoard,
-    ai_player: usize,
-    historic_moves: &Vec<Move>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let score = ai_controlled_board.relevant_mc_tree.mc_node.scores();
-    let win_rate_prev_player = score.0 as f64 / score.1 as f64;
-    let win_rate_ai = if ai_player == ai_controlled_board.board.turn % 2 {
-        1.0 - win_rate_prev_player
-    } else {
-        win_rate_prev_player
-    };
-    if win_rate_ai < 0.4 && score.1 > 300_000 {
-        wasm_bindgen_futures::spawn_local(add_table_ignore_error(
-            ai_controlled_board.board.clone(),
-            win_rate_ai,
-            score.1,
-            ai_player == 0,
-            historic_moves.clone(),
-        ));
-    }
-    Ok(())
-}
-async fn try_downloading_pre_calc(
-    board: &Board,
-) -> Result<MonteCarloTree, Box<dyn std::error::Error + Sync + Send>> {
+async fn try_downloading_pre_calc(board: &Board) -> Result<Vec<u8>, BookError> {
     let resp = reqwest::get(format!(
         "{}precalc/precalc/{}.mc_node",
         BASE_URL,
@@ -243,190 +189,126 @@ async fn try_downloading_pre_calc(
     ))
     .await?;
     if resp.status() == 200 {
-        let body = resp.bytes().await?;
-        Ok(MonteCarloTree::deserialize(&body))
+        Ok(resp.bytes().await?.to_vec())
     } else {
         return Err("not found")?;
     }
 }
 
-async fn take_game_move(
-    ai_controlled_board: &mut AIControlledBoard,
-    pre_calc: &PreCalc,
-    game_move: Move,
-    ai_player: usize,
-    mirror_calc_board: &mut Option<bool>,
-    historic_moves: &Vec<Move>,
-) {
-    ai_controlled_board.game_move(game_move);
-    if let Some((score_zero, pre_calc_mirrored)) =
-        pre_calc.roll_out_score(&ai_controlled_board.board)
-    {
-        let to_download = if pre_calc_mirrored {
-            Board::decode(&ai_controlled_board.board.encode_mirror()).unwrap()
-        } else {
-            ai_controlled_board.board.clone()
-        };
-        match try_downloading_pre_calc(&to_download).await {
-            Ok(mc_tree) => {
-                log::info!(
-                    "Found precalc {} with {} visits, is precalc mirror {}",
-                    to_download.encode(),
-                    mc_tree.mc_node.number_visits(),
-                    pre_calc_mirrored
-                );
-                ai_controlled_board.relevant_mc_tree = mc_tree;
-                ai_controlled_board.board = to_download;
-                //ai_controlled_board
-                //    .relevant_mc_tree
-                //    .select_best_move(&ai_controlled_board.board, &pre_calc);
-                *mirror_calc_board = match (*mirror_calc_board, pre_calc_mirrored) {
-                    (Some(value), true) => Some(!value),
-                    (Some(value), false) => Some(value),
-                    (None, _) => None,
-                };
-            }
-            Err(err) => {
-                log::warn!("{}", err);
-            }
-        }
-    } else {
-        if let Err(err) =
-            store_table_if_unknown_and_ai_loses(ai_controlled_board, ai_player, historic_moves)
-                .await
-        {
-            log::warn!("{}", err);
-        }
-    }
+async fn get_board_scores() -> Result<PreCalc, BookError> {
+    Ok(reqwest::get(format!("{}precalc/to_precalc.json", BASE_URL))
+        .await?
+        .json()
+        .await?)
 }
 
-async fn get_board_scores() -> Result<PreCalc, Box<dyn std::error::Error + Sync + Send>> {
-    Ok(reqwest::get(format!("{}precalc/to_precalc.json", BASE_URL))
+async fn get_manifest() -> Result<BookManifest, BookError> {
+    Ok(reqwest::get(format!("{}precalc/{}", BASE_URL, MANIFEST_FILE))
         .await?
         .json()
         .await?)
 }
-// Here we will put the actual worker code. This will be running the monte carlo simulations in the background.
-async fn internal_worker(user_commands: CommandChannel, calc_update_channel: WorkerUpdates) {
-    let mut ai_controlled_board = AIControlledBoard::decode("0;10E1;10E9").unwrap();
-    let pre_calc: PreCalc = match get_board_scores().await {
-        Ok(pre_calc) => pre_calc,
-        Err(err) => {
-            log::warn!("{}", err);
-            PreCalc::new()
+
+// Fetches `range` of the packed book. A server that ignores the range sends the whole file, then it is kept in `whole`
+// and this and all later ranges are cut out of it, instead of downloading the whole file for every tree.
+async fn download_packed_range(
+    whole: &mut Option<Vec<u8>>,
+    range: Range<usize>,
+) -> Result<Vec<u8>, BookError> {
+    if let Some(whole) = whole {
+        return whole
+            .get(range)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| "packed book is truncated".into());
+    }
+    let resp = reqwest::Client::new()
+        .get(format!("{}precalc/{}", BASE_URL, PACKED_BOOK_FILE))
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes={}-{}", range.start, range.end - 1),
+        )
+        .send()
+        .await?;
+    let bytes = match resp.status().as_u16() {
+        206 => resp.bytes().await?.to_vec(),
+        200 => {
+            log::info!("The server ignores range requests, keeping the whole packed book");
+            whole
+                .insert(resp.bytes().await?.to_vec())
+                .get(range.clone())
+                .unwrap_or_default()
+                .to_vec()
         }
+        status => return Err(format!("packed book not available: {}", status))?,
     };
-    if let Ok(rel_tree) = try_downloading_pre_calc(&ai_controlled_board.board).await {
-        ai_controlled_board.relevant_mc_tree = rel_tree;
+    if bytes.len() != range.len() {
+        return Err("packed book is truncated")?;
     }
-    let mut ai_player = None;
-
-    let mut mirror_calc_board: Option<bool> = None;
-    let mut new_command = false;
-    let mut historic_moves = vec![];
-    loop {
-        TimeoutFuture::new(10).await;
-        if let Some(next_command) = user_commands.recv_next() {
-            new_command = true;
-            //log::info!("Message from main thread: {:?}", next_command);
-            match next_command {
-                UserCommand::DecodeBoard => {
-                    log::info!("Decoding board");
-                    // decode the board
-                }
-                UserCommand::GameMove(game_move) => {
-                    log::info!("Game Move {:?}", game_move);
-                    if mirror_calc_board.is_none() {
-                        match game_move.mirror_move_type() {
-                            MirrorMoveType::Right => {
-                                mirror_calc_board = Some(true);
-                            }
-                            MirrorMoveType::Left => {
-                                mirror_calc_board = Some(false);
-                            }
-                            _ => {}
-                        }
-                    }
-
-                    historic_moves.push(game_move);
-                    let game_move = if mirror_calc_board == Some(true) {
-                        game_move.mirror_move()
-                    } else {
-                        game_move
-                    };
-                    take_game_move(
-                        &mut ai_controlled_board,
-                        &pre_calc,
-                        game_move,
-                        ai_player.unwrap(),
-                        &mut mirror_calc_board,
-                        &historic_moves,
-                    )
-                    .await;
-                    // make a game move
-                }
-                UserCommand::SetAIPlayer(player) => {
-                    log::info!("Setting AI Player to {}", player);
-                    ai_player = Some(player);
-                }
-            }
+    Ok(bytes)
+}
+
+async fn download_packed_index(whole: &mut Option<Vec<u8>>) -> Result<PackedIndex, BookError> {
+    let header = download_packed_range(whole, 0..HEADER_LEN).await?;
+    let len = PackedIndex::len_from_header(&header)?;
+    PackedIndex::parse(&download_packed_range(whole, 0..len).await?)
+}
+
+// The book is downloaded from the same place the site is served from. The trees come from the packed book with range
+// requests, if it isn't there they are downloaded one by one. Either way they are checked with the manifest, that is
+// downloaded together with the scores.
+#[derive(Default)]
+pub struct HttpBook {
+    // `None` until the first tree is asked for, `Some(None)` if there is no packed book.
+    packed_index: Option<Option<PackedIndex>>,
+    // The whole packed book, if the server sent all of it for a range.
+    packed_whole: Option<Vec<u8>>,
+    manifest: Option<BookManifest>,
+}
+
+impl BookFetcher for HttpBook {
+    async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError> {
+        match get_manifest().await {
+            Ok(manifest) => self.manifest = Some(manifest),
+            Err(err) => log::warn!("Not using the book trees, no manifest: {}", err),
         }
+        get_board_scores().await.map(Rc::new)
+    }
 
-        let number_visits = ai_controlled_board.relevant_mc_tree.mc_node.number_visits();
-        if (ai_controlled_board.is_played_out() || number_visits >= 20_000_000) && !new_command {
-            // Here we want to just wait for 100 ms and then continue, so to make it more responsive
-            TimeoutFuture::new(100).await;
-            continue;
+    async fn tree(&mut self, board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+        // Without a manifest there is nothing to tell, `verified_tree` refuses the tree anyway.
+        if self.manifest.as_ref().is_some_and(|manifest| !manifest.has_tree(&board.encode())) {
+            return Ok(None);
         }
-        let number_of_steps = if new_command { 100 } else { 10_000 };
-
-        let resp = ai_controlled_board.ai_move(number_of_steps, &pre_calc);
-        //log::info!("AI Move: {:?}", resp);
-
-        if number_visits > 600_000
-            || ai_controlled_board.is_played_out()
-            || resp.number_of_simulations >= 300_000
-        {
-            if ai_player == Some(ai_controlled_board.board.turn % 2) {
-                log::info!("AI TOOK MOVE IN WORKER Move: {:?}", resp.suggested_move);
-                let game_move = resp.suggested_move;
-                if mirror_calc_board.is_none() {
-                    match game_move.mirror_move_type() {
-                        MirrorMoveType::Right => {
-                            mirror_calc_board = Some(true);
-                        }
-                        MirrorMoveType::Left => {
-                            mirror_calc_board = Some(false);
-                        }
-                        _ => {}
-                    }
+        if self.packed_index.is_none() {
+            let index = match download_packed_index(&mut self.packed_whole).await {
+                Ok(index) => Some(index),
+                Err(err) => {
+                    log::warn!("Downloading the trees one by one: {}", err);
+                    None
                 }
-                let to_send = if mirror_calc_board == Some(true) {
-                    resp.suggested_move.mirror_move()
-                } else {
-                    resp.suggested_move
-                };
-                historic_moves.push(to_send);
-                calc_update_channel.send_update(CalculateUpdate::Finish(to_send));
-                take_game_move(
-                    &mut ai_controlled_board,
-                    &pre_calc,
-                    resp.suggested_move,
-                    ai_player.unwrap(),
-                    &mut mirror_calc_board,
-                    &historic_moves,
-                )
-                .await;
-            }
-            new_command = false;
-        } else {
-            new_command = false;
-            calc_update_channel
-                .send_update(CalculateUpdate::Progress(number_visits as f32 / 600_000.0));
+            };
+            self.packed_index = Some(index);
         }
+        let bytes = match &self.packed_index {
+            Some(Some(index)) => {
+                let encoding = board.encode();
+                let range = index.find(&encoding).ok_or("not found")?;
+                unpack(&encoding, &download_packed_range(&mut self.packed_whole, range).await?)?
+            }
+            _ => try_downloading_pre_calc(board).await?,
+        };
+        verified_tree(self.manifest.as_ref(), board, &bytes).map(Some)
     }
 }
 
+// Here we will put the actual worker code. This will be running the monte carlo simulations in the background.
+async fn internal_worker(user_commands: CommandChannel, calc_update_channel: WorkerUpdates) {
+    EngineSession::new(user_commands, calc_update_channel, HttpBook::default())
+        .await
+        .run()
+        .await;
+}
+
 // todo: on the dioxus side of things, we can make this a macro or something that writes the JS snippet automatically to
 // link it all together
 #[wasm_bindgen]
@@ -440,8 +322,7 @@ pub async fn start_webworker() {
     let scope = DedicatedWorkerGlobalScope::unchecked_from_js_ref(js_value);
     // let scope = WorkerGlobalScope::unchecked_from_js_ref(js_value);
 
-    let message_received = std::sync::Arc::new(std::sync::Mutex::new(VecDeque::new()));
-    let local_queu = message_received.clone();
+    let (sender, receiver) = unbounded();
 
     // Here we put messages send to the worker on the internal queu
     let f: Closure<dyn Fn(MessageEvent) -> ()> = Closure::new(move |event: MessageEvent| {
@@ -451,15 +332,17 @@ pub async fn start_webworker() {
         let mut bytes = vec![0; uint8_array.length() as usize];
         // Copy the contents of the Uint8Array into the Vec<u8>
         uint8_array.copy_to(&mut bytes);
-        let user_command: UserCommand = bincode::deserialize(&bytes).unwrap();
-        local_queu.lock().unwrap().push_back(user_command);
+        let (sent_at, user_command): (f64, UserCommand) = bincode::deserialize(&bytes).unwrap();
+        // Sending wakes up the worker loop if it is waiting for a command.
+        let _ = sender.unbounded_send((user_command, sent_at));
     });
     let val = f.into_js_value();
     let f = js_sys::Function::unchecked_from_js(val);
     scope.set_onmessage(Some(&f));
 
     let command_channel = CommandChannel {
-        user_commands: message_received.clone(),
+        user_commands: receiver,
+        latencies: vec![],
     };
     let worker_updates = WorkerUpdates {
         scope: scope.clone(),
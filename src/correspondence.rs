@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::BoardWithHistory;
+use crate::text_board::parse_move;
+
+// Bumped when the token changes, so an old token is refused instead of misread.
+const TOKEN_VERSION: &str = "q1";
+
+/// A game played by sending tokens back and forth, without a server. Every token has all moves, so the board can be
+/// set up from it alone.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CorrespondenceGame {
+    /// Tells games between the same players apart.
+    pub id: u32,
+    /// In Quoridor Strats notation.
+    pub moves: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenError {
+    /// Not a token, or one of another version.
+    Malformed,
+    /// The checksum doesn't match the moves, the token was changed or copied incompletely.
+    Tampered,
+    IllegalMove(String),
+    /// The token has fewer moves than the game is known to have, or misses moves in between.
+    OutOfOrder {
+        known: usize,
+        received: usize,
+    },
+    /// The token has another move than the game is known to have.
+    Diverged {
+        at: usize,
+    },
+    /// The new move of the token is one of the receiving player, only the opponent sends moves.
+    WrongSide,
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenError::Malformed => write!(f, "this is not a move token"),
+            TokenError::Tampered => write!(f, "the token was changed, its checksum doesn't match"),
+            TokenError::IllegalMove(notation) => write!(f, "{} is not a legal move", notation),
+            TokenError::OutOfOrder { known, received } => write!(
+                f,
+                "the token has {} moves, but the game has {} already, it's an old token or one went missing",
+                received, known
+            ),
+            TokenError::Diverged { at } => {
+                write!(f, "the token has another move {} than the game", at + 1)
+            }
+            TokenError::WrongSide => write!(f, "the new move of the token is for the side of the receiver"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+/// What a token that was accepted means for the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Received {
+    /// A game that wasn't known yet, the one who opened it plays the side to move.
+    NewGame,
+    /// The opponent made the next move.
+    NextMove,
+    /// Nothing new, like a token that was sent itself.
+    AlreadyKnown,
+}
+
+impl CorrespondenceGame {
+    pub fn new(id: u32) -> CorrespondenceGame {
+        CorrespondenceGame { id, moves: vec![] }
+    }
+
+    // Everything the checksum covers.
+    fn body(&self) -> String {
+        format!("{}-{:08x}-{}", TOKEN_VERSION, self.id, self.moves.join("."))
+    }
+
+    /// Like `q1-0001e240-e2.e8.d3h-9f3c1a2b`. The checksum catches changed and incomplete tokens, it's no signature: a
+    /// player who wants to cheat can compute it as well.
+    pub fn to_token(&self) -> String {
+        let body = self.body();
+        format!("{}-{:08x}", body, crc32fast::hash(body.as_bytes()))
+    }
+
+    /// Reads a token, and checks its checksum and that its moves are legal.
+    pub fn parse(token: &str) -> Result<CorrespondenceGame, TokenError> {
+        let token = token.trim();
+        let (body, checksum) = token.rsplit_once('-').ok_or(TokenError::Malformed)?;
+        let mut parts = body.splitn(3, '-');
+        if parts.next() != Some(TOKEN_VERSION) {
+            return Err(TokenError::Malformed);
+        }
+        let id = parts
+            .next()
+            .and_then(|id| u32::from_str_radix(id, 16).ok())
+            .ok_or(TokenError::Malformed)?;
+        let moves = parts.next().ok_or(TokenError::Malformed)?;
+        let checksum = u32::from_str_radix(checksum, 16).map_err(|_| TokenError::Malformed)?;
+        if crc32fast::hash(body.as_bytes()) != checksum {
+            return Err(TokenError::Tampered);
+        }
+        let game = CorrespondenceGame {
+            id,
+            moves: moves
+                .split('.')
+                .filter(|notation| !notation.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+        game.board()?;
+        Ok(game)
+    }
+
+    /// Replays the moves from the start.
+    pub fn board(&self) -> Result<BoardWithHistory, TokenError> {
+        let mut history = BoardWithHistory::new();
+        for notation in &self.moves {
+            let game_move = parse_move(&history.board, notation)
+                .ok_or_else(|| TokenError::IllegalMove(notation.clone()))?;
+            history.game_move(game_move);
+        }
+        Ok(history)
+    }
+
+    /// Whether this token can follow `known`, the same game as it was seen last. It has to have the same moves and at
+    /// most the one move of the opponent more.
+    pub fn check_order(&self, known: &CorrespondenceGame) -> Result<Received, TokenError> {
+        if let Some(at) = self
+            .moves
+            .iter()
+            .zip(&known.moves)
+            .position(|(received, known)| received != known)
+        {
+            return Err(TokenError::Diverged { at });
+        }
+        match self.moves.len() {
+            received if received == known.moves.len() => Ok(Received::AlreadyKnown),
+            received if received == known.moves.len() + 1 => Ok(Received::NextMove),
+            received => Err(TokenError::OutOfOrder {
+                known: known.moves.len(),
+                received,
+            }),
+        }
+    }
+}
+
+/// A correspondence game as one of the players has it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CorrespondenceRecord {
+    pub game: CorrespondenceGame,
+    /// The side this player plays, 0 for white.
+    pub player: usize,
+}
+
+/// All correspondence games of this player, by id.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CorrespondenceGames {
+    pub games: BTreeMap<u32, CorrespondenceRecord>,
+}
+
+impl CorrespondenceGames {
+    /// Starts a game as white.
+    pub fn start(&mut self, id: u32) {
+        self.games.insert(
+            id,
+            CorrespondenceRecord {
+                game: CorrespondenceGame::new(id),
+                player: 0,
+            },
+        );
+    }
+
+    /// Takes in a token from the opponent, a game that isn't known yet is joined as the side to move.
+    pub fn receive(&mut self, game: CorrespondenceGame) -> Result<Received, TokenError> {
+        let Some(record) = self.games.get_mut(&game.id) else {
+            let player = game.moves.len() % 2;
+            self.games
+                .insert(game.id, CorrespondenceRecord { game, player });
+            return Ok(Received::NewGame);
+        };
+        let received = game.check_order(&record.game)?;
+        // The move after the known ones is made by the side whose turn it was.
+        if received == Received::NextMove && record.game.moves.len() % 2 == record.player {
+            return Err(TokenError::WrongSide);
+        }
+        record.game = game;
+        Ok(received)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(moves: &[&str]) -> CorrespondenceGame {
+        CorrespondenceGame {
+            id: 7,
+            moves: moves.iter().map(|notation| notation.to_string()).collect(),
+        }
+    }
+
+    // Known to this player as white, after the moves so far.
+    fn white_with(moves: &[&str]) -> CorrespondenceGames {
+        let mut games = CorrespondenceGames::default();
+        games.start(7);
+        games.games.get_mut(&7).unwrap().game = game(moves);
+        games
+    }
+
+    #[test]
+    fn a_token_reads_back_as_the_same_game() {
+        let sent = game(&["e2", "e8", "d3h"]);
+        assert_eq!(CorrespondenceGame::parse(&sent.to_token()), Ok(sent));
+    }
+
+    #[test]
+    fn a_changed_token_is_tampered() {
+        let token = game(&["e2", "e8"]).to_token().replacen(".e8", ".d9", 1);
+        assert_eq!(CorrespondenceGame::parse(&token), Err(TokenError::Tampered));
+    }
+
+    #[test]
+    fn the_move_of_the_opponent_is_received() {
+        let mut games = white_with(&["e2"]);
+        assert_eq!(games.receive(game(&["e2", "e8"])), Ok(Received::NextMove));
+        assert_eq!(games.games[&7].game, game(&["e2", "e8"]));
+    }
+
+    #[test]
+    fn an_old_token_is_out_of_order() {
+        let mut games = white_with(&["e2", "e8", "e3"]);
+        assert_eq!(
+            games.receive(game(&["e2", "e8"])),
+            Err(TokenError::OutOfOrder {
+                known: 3,
+                received: 2
+            })
+        );
+    }
+
+    #[test]
+    fn a_token_that_skips_a_move_is_out_of_order() {
+        let mut games = white_with(&["e2"]);
+        assert_eq!(
+            games.receive(game(&["e2", "e8", "e3", "e7"])),
+            Err(TokenError::OutOfOrder {
+                known: 1,
+                received: 4
+            })
+        );
+    }
+
+    #[test]
+    fn another_earlier_move_is_diverged() {
+        let mut games = white_with(&["e2"]);
+        assert_eq!(
+            games.receive(game(&["d1", "e8"])),
+            Err(TokenError::Diverged { at: 0 })
+        );
+        assert_eq!(games.games[&7].game, game(&["e2"]));
+    }
+
+    #[test]
+    fn a_move_for_the_own_side_is_refused() {
+        let mut games = white_with(&["e2", "e8"]);
+        assert_eq!(
+            games.receive(game(&["e2", "e8", "e3"])),
+            Err(TokenError::WrongSide)
+        );
+        assert_eq!(games.games[&7].game, game(&["e2", "e8"]));
+    }
+
+    #[test]
+    fn a_joined_game_is_played_as_the_side_to_move() {
+        let mut games = CorrespondenceGames::default();
+        assert_eq!(games.receive(game(&["e2"])), Ok(Received::NewGame));
+        assert_eq!(games.games[&7].player, 1);
+        assert_eq!(
+            games.receive(game(&["e2", "e8"])),
+            Err(TokenError::WrongSide)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use dioxus::prelude::*;
+
+use quoridor::*;
+
+use quoridor_frontend::correspondence::{CorrespondenceGame, CorrespondenceGames, Received, TokenError};
+use quoridor_frontend::engine::now_millis;
+use quoridor_frontend::grid::winner;
+use quoridor_frontend::i18n::Locale;
+use quoridor_frontend::share::SharedLink;
+
+use crate::board_fr::{white_at_bottom, BoardGrid, OrientationToggle};
+use crate::settings::use_locale;
+use crate::share_link::ShareLink;
+use crate::storage;
+
+const CORRESPONDENCE_GAMES_KEY: &str = "correspondence_games";
+
+fn token_error_text(locale: Locale, err: &TokenError) -> String {
+    match err {
+        TokenError::Malformed => locale.text("token_malformed").to_string(),
+        TokenError::Tampered => locale.text("token_tampered").to_string(),
+        TokenError::IllegalMove(notation) => locale.format("token_illegal_move", &[("move", notation)]),
+        TokenError::OutOfOrder { known, received } => locale.format(
+            "token_out_of_order",
+            &[("known", &locale.count(*known as u64)), ("received", &locale.count(*received as u64))],
+        ),
+        TokenError::Diverged { at } => locale.format("token_diverged", &[("move", &locale.count(*at as u64 + 1))]),
+        TokenError::WrongSide => locale.text("token_wrong_side").to_string(),
+    }
+}
+
+// A pasted link or the bare token.
+fn read_token(text: &str) -> Result<CorrespondenceGame, TokenError> {
+    match SharedLink::parse(text) {
+        Some(SharedLink::Correspondence(token)) => CorrespondenceGame::parse(&token),
+        _ => CorrespondenceGame::parse(text),
+    }
+}
+
+#[derive(Props, PartialEq)]
+pub struct CorrespondencePlayProps {
+    /// The token of the link the page was opened with.
+    #[props(!optional)]
+    token: Option<String>,
+}
+
+// Slow games without a server: every move gives a link with all moves of the game, the opponent opens it, moves and
+// sends a link back. The games are kept between visits, so an old or changed link is noticed.
+pub fn CorrespondencePlay(cx: Scope<CorrespondencePlayProps>) -> Element {
+    let games: &UseRef<CorrespondenceGames> =
+        use_ref(cx, || storage::load(CORRESPONDENCE_GAMES_KEY).unwrap_or_default());
+    let current: &UseState<Option<u32>> = use_state(cx, || None);
+    let pasted = use_state(cx, String::new);
+    // What happened to the last token that was opened.
+    let message: &UseState<Option<String>> = use_state(cx, || None);
+    let board_flipped = use_state(cx, || false);
+    let locale = use_locale(cx);
+
+    let open = move |text: &str| {
+        let received = read_token(text).and_then(|game| {
+            let id = game.id;
+            games.with_mut(|games| games.receive(game)).map(|received| (id, received))
+        });
+        match received {
+            Ok((id, received)) => {
+                storage::save(CORRESPONDENCE_GAMES_KEY, &*games.read());
+                let player = games.read().games[&id].player;
+                board_flipped.set(white_at_bottom(false) != (player == 0));
+                current.set(Some(id));
+                message.set(Some(
+                    locale
+                        .text(match received {
+                            Received::NewGame => "corr_joined",
+                            Received::NextMove => "corr_next_move",
+                            Received::AlreadyKnown => "corr_known",
+                        })
+                        .to_string(),
+                ));
+            }
+            Err(err) => message.set(Some(token_error_text(locale, &err))),
+        }
+    };
+
+    use_effect(cx, (&cx.props.token,), |(token,)| {
+        if let Some(token) = token {
+            open(&token);
+        }
+        async {}
+    });
+
+    let record = current.get().and_then(|id| games.read().games.get(&id).cloned());
+    let history = record.as_ref().and_then(|record| record.game.board().ok());
+
+    cx.render(rsx! {
+        div { class: "page-columns flex flex-col justify-center items-center gap-4",
+        if let (Some(record), Some(history)) = (&record, history) {
+            let board = history.board.clone();
+            let won = winner(&board);
+            let my_turn = won.is_none() && board.turn % 2 == record.player;
+            let id = record.game.id;
+            let status = match won {
+                Some(player) => locale.format("corr_won", &[("player", locale.player(player))]),
+                None if my_turn => locale.format("corr_your_turn", &[("player", locale.player(record.player))]),
+                None => locale.text("corr_waiting").to_string(),
+            };
+            let link = SharedLink::Correspondence(record.game.to_token());
+            rsx! {
+                div { class: "flex flex-col items-center",
+                    BoardGrid {
+                        board: board,
+                        highlights: history.last_moves(history.moves.len()),
+                        flipped: *board_flipped.get(),
+                        interactive: my_turn,
+                        on_move: move |game_move: Move| {
+                            games.with_mut(|games| {
+                                if let Some(record) = games.games.get_mut(&id) {
+                                    if let Ok(history) = record.game.board() {
+                                        record.game.moves.push(game_move.to_quoridor_strat_notation(&history.board));
+                                    }
+                                }
+                            });
+                            storage::save(CORRESPONDENCE_GAMES_KEY, &*games.read());
+                            message.set(None);
+                        },
+                    },
+                    div { class: "w-full p-4 flex flex-col items-center",
+                        div { class: "text-2xl font-semibold", locale.text("moves_history") },
+                        div { class: "text-xl", "{history.historic_moves()}" }
+                    }
+                },
+                div { class: "side-panel flex flex-col space-y-2 w-full p-2",
+                    div { class: "text-xl font-semibold", "{status}" },
+                    if !my_turn && won.is_none() {
+                        rsx! { ShareLink { link: link } }
+                    }
+                    OrientationToggle { flipped: board_flipped },
+                }
+            }
+        }
+        div { class: "side-panel flex flex-col space-y-2 w-full p-2",
+            div { class: "text-sm", locale.text("corr_intro") },
+            if let Some(message) = message.get() {
+                rsx! { div { class: "bg-amber-400 font-bold py-2 px-4 rounded", "{message}" } }
+            }
+            div { class: "flex space-x-2",
+                input {
+                    class: "border rounded px-2 text-black w-full",
+                    placeholder: locale.text("corr_paste"),
+                    value: "{pasted}",
+                    oninput: move |evt| pasted.set(evt.value.clone()),
+                },
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| {
+                        open(pasted.get());
+                        pasted.set(String::new());
+                    },
+                    locale.text("corr_open")
+                },
+            },
+            button {
+                class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                onclick: move |_| {
+                    // The time is only there to tell the games of the same players apart.
+                    let id = now_millis() as u64 as u32;
+                    games.with_mut(|games| games.start(id));
+                    storage::save(CORRESPONDENCE_GAMES_KEY, &*games.read());
+                    board_flipped.set(!white_at_bottom(false));
+                    current.set(Some(id));
+                    message.set(None);
+                },
+                locale.text("corr_new_game")
+            },
+            games.read().games.values().rev().map(|record| {
+                let id = record.game.id;
+                let label = locale.format(
+                    "corr_game",
+                    &[("id", &format!("{:08x}", id)), ("moves", &locale.count(record.game.moves.len() as u64))],
+                );
+                let class = if *current.get() == Some(id) {
+                    "bg-amber-700 text-white font-bold py-2 px-4 rounded"
+                } else {
+                    "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded"
+                };
+                let flipped = white_at_bottom(false) != (record.player == 0);
+                rsx! {
+                    button {
+                        key: "{id}",
+                        class: class,
+                        onclick: move |_| {
+                            board_flipped.set(flipped);
+                            current.set(Some(id));
+                            message.set(None);
+                        },
+                        "{label}"
+                    }
+                }
+            })
+        }
+        }
+    })
+}
@@ -0,0 +1,873 @@
+use quoridor::*;
+
+use crate::grid::{MoveHighlight, DIMENSION};
+use crate::text_board::{column_label, row_label};
+
+// In pixels at scale 1. A square is 40 wide and a groove 10, like the 4 to 1 of the board on the page.
+const SQUARE: f32 = 40.0;
+const GROOVE: f32 = 10.0;
+const PITCH: f32 = SQUARE + GROOVE;
+// The squares with a groove all around them.
+const BOARD: f32 = DIMENSION as f32 * PITCH + GROOVE;
+const PAWN_RADIUS: f32 = 16.0;
+const PAWN_OUTLINE: f32 = 2.0;
+const ARROW_WIDTH: f32 = 6.0;
+const ARROW_HEAD: f32 = 16.0;
+const LABEL_MARGIN: f32 = 24.0;
+const LABEL_SIZE: f32 = 14.0;
+const CAPTION_HEIGHT: f32 = 32.0;
+const CAPTION_SIZE: f32 = 14.0;
+
+/// The colors of the classic theme, a diagram looks the same whatever theme the page uses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Color(u8, u8, u8, u8);
+
+impl Color {
+    const BACKGROUND: Color = Color(255, 255, 255, 255);
+    const GROOVE: Color = Color(251, 191, 36, 255);
+    const SQUARE: Color = Color(217, 119, 6, 255);
+    const WALL: Color = Color(146, 64, 14, 255);
+    const PAWNS: [Color; 2] = [Color(241, 245, 249, 255), Color(15, 23, 42, 255)];
+    const OUTLINE: Color = Color(15, 23, 42, 255);
+    const ARROW: Color = Color(220, 38, 38, 200);
+    const TEXT: Color = Color(0, 0, 0, 255);
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    fn opacity(&self) -> f32 {
+        self.3 as f32 / 255.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Color,
+    },
+    Circle {
+        x: f32,
+        y: f32,
+        radius: f32,
+        color: Color,
+    },
+    Line {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: Color,
+    },
+    Polygon {
+        points: Vec<(f32, f32)>,
+        color: Color,
+    },
+    /// Centered on `x` and `y`, `size` is the height of the letters.
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        text: String,
+        color: Color,
+    },
+}
+
+/// What to draw next to the position.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct DiagramOptions {
+    /// Turns the board around, like `flipped` of the board on the page.
+    pub flipped: bool,
+    /// The column letters under the board and the row numbers left of it.
+    pub coordinates: bool,
+    /// A move to point out, an arrow for a pawn move and a red wall for a wall.
+    pub arrow: Option<Move>,
+    /// The encoding of the position under the board, so it can be set up again.
+    pub caption: bool,
+}
+
+/// A position drawn for documentation, as an SVG or a PNG. Both are made from the same shapes, so they show the same.
+#[derive(Clone, Debug)]
+pub struct Diagram {
+    width: f32,
+    height: f32,
+    shapes: Vec<Shape>,
+}
+
+impl Diagram {
+    pub fn new(board: &Board, options: &DiagramOptions) -> Diagram {
+        let left = if options.coordinates {
+            LABEL_MARGIN
+        } else {
+            0.0
+        };
+        let bottom = if options.coordinates {
+            LABEL_MARGIN
+        } else {
+            0.0
+        };
+        let caption = if options.caption { CAPTION_HEIGHT } else { 0.0 };
+        let width = left + BOARD;
+        let height = BOARD + bottom + caption;
+        // The top left corner of a square.
+        let square = |row: i8, col: i8| {
+            let shown_row = if options.flipped {
+                DIMENSION as i8 - 1 - row
+            } else {
+                row
+            };
+            (
+                left + GROOVE + col as f32 * PITCH,
+                GROOVE + shown_row as f32 * PITCH,
+            )
+        };
+        let center = |position: Position| {
+            let (x, y) = square(position.row, position.col);
+            (x + SQUARE / 2.0, y + SQUARE / 2.0)
+        };
+        // A wall starts at its position and covers the groove next to that square and the square after it.
+        let wall = |direction: WallDirection, position: Position, color: Color| {
+            let (x, y) = square(position.row, position.col);
+            // The upper one of the two rows the wall touches, as it's shown.
+            let y = if options.flipped { y - PITCH } else { y };
+            match direction {
+                WallDirection::Horizontal => Shape::Rect {
+                    x,
+                    y: y + SQUARE,
+                    width: 2.0 * SQUARE + GROOVE,
+                    height: GROOVE,
+                    color,
+                },
+                WallDirection::Vertical => Shape::Rect {
+                    x: x + SQUARE,
+                    y,
+                    width: GROOVE,
+                    height: 2.0 * SQUARE + GROOVE,
+                    color,
+                },
+            }
+        };
+
+        let mut shapes = vec![
+            Shape::Rect {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+                color: Color::BACKGROUND,
+            },
+            Shape::Rect {
+                x: left,
+                y: 0.0,
+                width: BOARD,
+                height: BOARD,
+                color: Color::GROOVE,
+            },
+        ];
+        for row in 0..DIMENSION as i8 {
+            for col in 0..DIMENSION as i8 {
+                let (x, y) = square(row, col);
+                shapes.push(Shape::Rect {
+                    x,
+                    y,
+                    width: SQUARE,
+                    height: SQUARE,
+                    color: Color::SQUARE,
+                });
+            }
+        }
+        for direction in [WallDirection::Horizontal, WallDirection::Vertical] {
+            for row in 0..DIMENSION as i8 - 1 {
+                for col in 0..DIMENSION as i8 - 1 {
+                    let position = Position { row, col };
+                    if board.walls.is_allowed(direction, position) {
+                        shapes.push(wall(direction, position, Color::WALL));
+                    }
+                }
+            }
+        }
+        for (player, pawn) in board.pawns.iter().enumerate() {
+            let (x, y) = center(pawn.position);
+            shapes.push(Shape::Circle {
+                x,
+                y,
+                radius: PAWN_RADIUS + PAWN_OUTLINE,
+                color: Color::OUTLINE,
+            });
+            shapes.push(Shape::Circle {
+                x,
+                y,
+                radius: PAWN_RADIUS,
+                color: Color::PAWNS[player],
+            });
+        }
+
+        match options
+            .arrow
+            .map(|game_move| MoveHighlight::new(board, game_move))
+        {
+            Some(MoveHighlight::Pawn { from, to }) => {
+                let (from, to) = (center(from), center(to));
+                let length = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+                let along = ((to.0 - from.0) / length, (to.1 - from.1) / length);
+                let across = (-along.1, along.0);
+                // The shaft stops where the head starts, so the point stays sharp.
+                let neck = (to.0 - along.0 * ARROW_HEAD, to.1 - along.1 * ARROW_HEAD);
+                shapes.push(Shape::Line {
+                    from,
+                    to: neck,
+                    width: ARROW_WIDTH,
+                    color: Color::ARROW,
+                });
+                shapes.push(Shape::Polygon {
+                    points: vec![
+                        to,
+                        (
+                            neck.0 + across.0 * ARROW_HEAD / 2.0,
+                            neck.1 + across.1 * ARROW_HEAD / 2.0,
+                        ),
+                        (
+                            neck.0 - across.0 * ARROW_HEAD / 2.0,
+                            neck.1 - across.1 * ARROW_HEAD / 2.0,
+                        ),
+                    ],
+                    color: Color::ARROW,
+                });
+            }
+            Some(MoveHighlight::Wall(direction, position)) => {
+                shapes.push(wall(direction, position, Color::ARROW));
+            }
+            None => {}
+        }
+
+        if options.coordinates {
+            for index in 0..DIMENSION {
+                let (x, y) = center(Position {
+                    row: index as i8,
+                    col: index as i8,
+                });
+                shapes.push(Shape::Text {
+                    x,
+                    y: BOARD + LABEL_MARGIN / 2.0,
+                    size: LABEL_SIZE,
+                    text: column_label(index),
+                    color: Color::TEXT,
+                });
+                shapes.push(Shape::Text {
+                    x: LABEL_MARGIN / 2.0,
+                    y,
+                    size: LABEL_SIZE,
+                    text: row_label(index),
+                    color: Color::TEXT,
+                });
+            }
+        }
+        if options.caption {
+            shapes.push(Shape::Text {
+                x: width / 2.0,
+                y: BOARD + bottom + CAPTION_HEIGHT / 2.0,
+                size: CAPTION_SIZE,
+                text: board.encode(),
+                color: Color::TEXT,
+            });
+        }
+
+        Diagram {
+            width,
+            height,
+            shapes,
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            self.width, self.height
+        );
+        for shape in &self.shapes {
+            let element = match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+                    x,
+                    y,
+                    width,
+                    height,
+                    color.hex(),
+                    color.opacity()
+                ),
+                Shape::Circle {
+                    x,
+                    y,
+                    radius,
+                    color,
+                } => format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+                    x,
+                    y,
+                    radius,
+                    color.hex(),
+                    color.opacity()
+                ),
+                Shape::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                } => format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-opacity=\"{}\" stroke-width=\"{}\"/>",
+                    from.0,
+                    from.1,
+                    to.0,
+                    to.1,
+                    color.hex(),
+                    color.opacity(),
+                    width
+                ),
+                Shape::Polygon { points, color } => format!(
+                    "<polygon points=\"{}\" fill=\"{}\" fill-opacity=\"{}\"/>",
+                    points
+                        .iter()
+                        .map(|(x, y)| format!("{},{}", x, y))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    color.hex(),
+                    color.opacity()
+                ),
+                Shape::Text {
+                    x,
+                    y,
+                    size,
+                    text,
+                    color,
+                } => format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" text-anchor=\"middle\" \
+                     dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                    x,
+                    y,
+                    // The font size is the height of the whole line, the capitals are about this much of it.
+                    size / 0.7,
+                    color.hex(),
+                    escape_xml(text)
+                ),
+            };
+            svg.push_str("  ");
+            svg.push_str(&element);
+            svg.push('\n');
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Draws the diagram without any fonts or libraries, `scale` times as large as the SVG. The text uses a small
+    /// built in font with only the letters of coordinates and encodings. The image is at least one pixel, however small
+    /// `scale` is.
+    pub fn to_png(&self, scale: f32) -> Vec<u8> {
+        let mut canvas = Canvas::new(
+            ((self.width * scale).ceil() as usize).max(1),
+            ((self.height * scale).ceil() as usize).max(1),
+        );
+        for shape in &self.shapes {
+            match shape {
+                Shape::Text {
+                    x,
+                    y,
+                    size,
+                    text,
+                    color,
+                } => {
+                    for (x, y, width, height) in text_pixels(*x, *y, *size, text) {
+                        canvas.fill(scale, x, y, x + width, y + height, *color, |_, _| true);
+                    }
+                }
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => canvas.fill(scale, *x, *y, x + width, y + height, *color, |_, _| true),
+                Shape::Circle {
+                    x,
+                    y,
+                    radius,
+                    color,
+                } => canvas.fill(
+                    scale,
+                    x - radius,
+                    y - radius,
+                    x + radius,
+                    y + radius,
+                    *color,
+                    |px, py| (px - x).powi(2) + (py - y).powi(2) <= radius * radius,
+                ),
+                Shape::Line {
+                    from,
+                    to,
+                    width,
+                    color,
+                } => {
+                    let margin = width / 2.0;
+                    canvas.fill(
+                        scale,
+                        from.0.min(to.0) - margin,
+                        from.1.min(to.1) - margin,
+                        from.0.max(to.0) + margin,
+                        from.1.max(to.1) + margin,
+                        *color,
+                        |px, py| distance_to_segment((px, py), *from, *to) <= margin,
+                    )
+                }
+                Shape::Polygon { points, color } => {
+                    let xs = points.iter().map(|point| point.0);
+                    let ys = points.iter().map(|point| point.1);
+                    canvas.fill(
+                        scale,
+                        xs.clone().fold(f32::MAX, f32::min),
+                        ys.clone().fold(f32::MAX, f32::min),
+                        xs.fold(f32::MIN, f32::max),
+                        ys.fold(f32::MIN, f32::max),
+                        *color,
+                        |px, py| inside_polygon((px, py), points),
+                    )
+                }
+            }
+        }
+        encode_png(canvas.width, canvas.height, &canvas.pixels)
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn distance_to_segment(point: (f32, f32), from: (f32, f32), to: (f32, f32)) -> f32 {
+    let segment = (to.0 - from.0, to.1 - from.1);
+    let length = segment.0 * segment.0 + segment.1 * segment.1;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((point.0 - from.0) * segment.0 + (point.1 - from.1) * segment.1) / length).clamp(0.0, 1.0)
+    };
+    let closest = (from.0 + t * segment.0, from.1 + t * segment.1);
+    ((point.0 - closest.0).powi(2) + (point.1 - closest.1).powi(2)).sqrt()
+}
+
+fn inside_polygon(point: (f32, f32), points: &[(f32, f32)]) -> bool {
+    let mut inside = false;
+    let mut previous = points[points.len() - 1];
+    for &current in points {
+        if (current.1 > point.1) != (previous.1 > point.1)
+            && point.0
+                < (previous.0 - current.0) * (point.1 - current.1) / (previous.1 - current.1)
+                    + current.0
+        {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
+
+// Every glyph is 5 pixels wide and 7 high, a row per byte with the leftmost pixel in the fifth bit.
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'h' => [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11],
+        'v' => [0x00, 0x00, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        ';' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08],
+        c if c.is_ascii_lowercase() => glyph(c.to_ascii_uppercase()),
+        _ => [0; 7],
+    }
+}
+
+// The pixels of the glyphs of `text` as rectangles, in the units of the diagram.
+fn text_pixels(x: f32, y: f32, size: f32, text: &str) -> Vec<(f32, f32, f32, f32)> {
+    let pixel = size / 7.0;
+    // A column of space after every glyph but the last.
+    let width = (text.chars().count() * 6).saturating_sub(1) as f32 * pixel;
+    let (left, top) = (x - width / 2.0, y - size / 2.0);
+    let mut pixels = vec![];
+    for (index, c) in text.chars().enumerate() {
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..5 {
+                if bits & (0x10 >> col) != 0 {
+                    pixels.push((
+                        left + (index * 6 + col) as f32 * pixel,
+                        top + row as f32 * pixel,
+                        pixel,
+                        pixel,
+                    ));
+                }
+            }
+        }
+    }
+    pixels
+}
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    /// RGBA, row by row.
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    // Every pixel is sampled this many times in both directions, to smooth the edges.
+    const SAMPLES: usize = 4;
+
+    fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+
+    // Paints `color` where `inside` is true, `inside` gets points in the units of the diagram and is only asked
+    // within the bounds.
+    #[allow(clippy::too_many_arguments)]
+    fn fill(
+        &mut self,
+        scale: f32,
+        left: f32,
+        top: f32,
+        right: f32,
+        bottom: f32,
+        color: Color,
+        inside: impl Fn(f32, f32) -> bool,
+    ) {
+        let first_col = ((left * scale).floor().max(0.0) as usize).min(self.width);
+        let last_col = ((right * scale).ceil().max(0.0) as usize).min(self.width);
+        let first_row = ((top * scale).floor().max(0.0) as usize).min(self.height);
+        let last_row = ((bottom * scale).ceil().max(0.0) as usize).min(self.height);
+        let step = 1.0 / Canvas::SAMPLES as f32;
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                let mut covered = 0;
+                for sample_row in 0..Canvas::SAMPLES {
+                    for sample_col in 0..Canvas::SAMPLES {
+                        let px = (col as f32 + (sample_col as f32 + 0.5) * step) / scale;
+                        let py = (row as f32 + (sample_row as f32 + 0.5) * step) / scale;
+                        if px >= left && px < right && py >= top && py < bottom && inside(px, py) {
+                            covered += 1;
+                        }
+                    }
+                }
+                if covered > 0 {
+                    let coverage = covered as f32 / (Canvas::SAMPLES * Canvas::SAMPLES) as f32;
+                    self.blend(row, col, color, coverage * color.opacity());
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, row: usize, col: usize, color: Color, alpha: f32) {
+        let index = (row * self.width + col) * 4;
+        let pixel = &mut self.pixels[index..index + 4];
+        let below = pixel[3] as f32 / 255.0;
+        let out = alpha + below * (1.0 - alpha);
+        if out == 0.0 {
+            return;
+        }
+        for (channel, value) in [color.0, color.1, color.2].into_iter().enumerate() {
+            let mixed =
+                (value as f32 * alpha + pixel[channel] as f32 * below * (1.0 - alpha)) / out;
+            pixel[channel] = mixed.round() as u8;
+        }
+        pixel[3] = (out * 255.0).round() as u8;
+    }
+}
+
+// A PNG of 8 bit RGBA pixels, without filtering.
+fn encode_png(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32fast::hash(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, the standard filters, not interlaced.
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut rows = Vec::with_capacity((width * 4 + 1) * height);
+    for row in rgba.chunks(width * 4) {
+        // Every row starts with its filter, 0 is none.
+        rows.push(0);
+        rows.extend_from_slice(row);
+    }
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    chunk(&mut png, b"IHDR", &header);
+    chunk(
+        &mut png,
+        b"IDAT",
+        &miniz_oxide::deflate::compress_to_vec_zlib(&rows, 6),
+    );
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_board::parse_move;
+
+    // The IHDR fields and the RGBA pixels of a PNG made by `encode_png`, checking the chunks on the way.
+    fn decode_png(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut at = 8;
+        let mut chunks = vec![];
+        while at < png.len() {
+            let length = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+            let kind = &png[at + 4..at + 8];
+            let data = &png[at + 8..at + 8 + length];
+            let crc =
+                u32::from_be_bytes(png[at + 8 + length..at + 12 + length].try_into().unwrap());
+            assert_eq!(crc, crc32fast::hash(&png[at + 4..at + 8 + length]));
+            chunks.push((kind.to_vec(), data.to_vec()));
+            at += 12 + length;
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| &kind[..]).collect();
+        assert_eq!(kinds, [&b"IHDR"[..], b"IDAT", b"IEND"]);
+
+        let header = &chunks[0].1;
+        let width = u32::from_be_bytes(header[0..4].try_into().unwrap());
+        let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        assert_eq!(&header[8..], [8, 6, 0, 0, 0]);
+
+        let rows = miniz_oxide::inflate::decompress_to_vec_zlib(&chunks[1].1).unwrap();
+        assert_eq!(rows.len(), (width as usize * 4 + 1) * height as usize);
+        let mut pixels = vec![];
+        for row in rows.chunks(width as usize * 4 + 1) {
+            assert_eq!(row[0], 0);
+            pixels.extend_from_slice(&row[1..]);
+        }
+        (width, height, pixels)
+    }
+
+    fn pixel(png: &(u32, u32, Vec<u8>), x: f32, y: f32) -> Color {
+        let index = (y as usize * png.0 as usize + x as usize) * 4;
+        let rgba = &png.2[index..index + 4];
+        Color(rgba[0], rgba[1], rgba[2], rgba[3])
+    }
+
+    #[test]
+    fn the_png_has_the_size_and_colors_of_the_shapes() {
+        let diagram = Diagram {
+            width: 20.0,
+            height: 10.0,
+            shapes: vec![
+                Shape::Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 20.0,
+                    height: 10.0,
+                    color: Color::BACKGROUND,
+                },
+                Shape::Rect {
+                    x: 10.0,
+                    y: 0.0,
+                    width: 10.0,
+                    height: 10.0,
+                    color: Color::SQUARE,
+                },
+                Shape::Circle {
+                    x: 5.0,
+                    y: 5.0,
+                    radius: 3.0,
+                    color: Color::PAWNS[1],
+                },
+                Shape::Polygon {
+                    points: vec![(12.0, 2.0), (18.0, 2.0), (12.0, 8.0)],
+                    color: Color::ARROW,
+                },
+            ],
+        };
+
+        let png = decode_png(&diagram.to_png(2.0));
+        assert_eq!((png.0, png.1), (40, 20));
+        assert_eq!(pixel(&png, 1.0, 1.0), Color::BACKGROUND);
+        assert_eq!(pixel(&png, 10.0, 10.0), Color::PAWNS[1]);
+        assert_eq!(pixel(&png, 38.0, 18.0), Color::SQUARE);
+        // The arrow color is see-through, so it's mixed with the square under it.
+        let arrow = pixel(&png, 26.0, 6.0);
+        assert_ne!(arrow, Color::SQUARE);
+        assert!(arrow.0 > Color::SQUARE.0 && arrow.1 < Color::SQUARE.1);
+        assert_eq!(arrow.3, 255);
+        // Outside the triangle, on the far side of its long edge.
+        assert_eq!(pixel(&png, 34.0, 14.0), Color::SQUARE);
+    }
+
+    #[test]
+    fn the_png_of_a_position_shows_its_pawns() {
+        let board = Board::decode("0;10E1;10E9").unwrap();
+        let diagram = Diagram::new(&board, &DiagramOptions::default());
+        let png = decode_png(&diagram.to_png(1.0));
+
+        assert_eq!((png.0, png.1), (BOARD as u32, BOARD as u32));
+        for player in 0..2 {
+            let (x, y) = diagram
+                .shapes
+                .iter()
+                .find_map(|shape| match shape {
+                    Shape::Circle { x, y, color, .. } if *color == Color::PAWNS[player] => {
+                        Some((*x, *y))
+                    }
+                    _ => None,
+                })
+                .unwrap();
+            assert_eq!(pixel(&png, x, y), Color::PAWNS[player]);
+        }
+        assert_eq!(pixel(&png, GROOVE / 2.0, GROOVE / 2.0), Color::GROOVE);
+        assert_eq!(pixel(&png, GROOVE + 2.0, GROOVE + 2.0), Color::SQUARE);
+    }
+
+    fn rects(diagram: &Diagram, wanted: Color) -> Vec<(f32, f32, f32, f32)> {
+        diagram
+            .shapes
+            .iter()
+            .filter_map(|shape| match shape {
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } if *color == wanted => Some((*x, *y, *width, *height)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn arrow_line(diagram: &Diagram) -> ((f32, f32), (f32, f32)) {
+        diagram
+            .shapes
+            .iter()
+            .find_map(|shape| match shape {
+                Shape::Line { from, to, .. } => Some((*from, *to)),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn a_flipped_diagram_turns_the_walls_and_arrows_around() {
+        let board = Board::decode("4;9E3;10E7;D6h").unwrap();
+        let pawn_move = parse_move(&board, "e4").unwrap();
+        let options = DiagramOptions {
+            arrow: Some(pawn_move),
+            ..DiagramOptions::default()
+        };
+        let flipped_options = DiagramOptions {
+            flipped: true,
+            ..options
+        };
+        let diagram = Diagram::new(&board, &options);
+        let flipped = Diagram::new(&board, &flipped_options);
+
+        // Turned around the middle of the board, a wall keeps its columns and ends up as far from the bottom as it
+        // was from the top.
+        let walls = rects(&diagram, Color::WALL);
+        assert_eq!(walls.len(), 1);
+        let (x, y, width, height) = walls[0];
+        assert_eq!(width, 2.0 * SQUARE + GROOVE);
+        assert_eq!(
+            rects(&flipped, Color::WALL),
+            vec![(x, BOARD - y - height, width, height)]
+        );
+
+        let (from, to) = arrow_line(&diagram);
+        let (flipped_from, flipped_to) = arrow_line(&flipped);
+        assert_eq!(flipped_from, (from.0, BOARD - from.1));
+        assert_eq!(flipped_to, (to.0, BOARD - to.1));
+        // The arrow points the other way up or down on the flipped board.
+        assert!((to.1 - from.1) * (flipped_to.1 - flipped_from.1) < 0.0);
+
+        let svg = flipped.to_svg();
+        assert!(svg.contains(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"",
+            x,
+            BOARD - y - height,
+            width,
+            height,
+            Color::WALL.hex()
+        )));
+        assert!(svg.contains(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\"",
+            flipped_from.0,
+            flipped_from.1,
+            flipped_to.0,
+            flipped_to.1,
+            Color::ARROW.hex()
+        )));
+        assert_eq!(svg.matches("<polygon").count(), 1);
+    }
+
+    #[test]
+    fn a_flipped_diagram_puts_a_suggested_wall_in_the_same_place_as_the_walls() {
+        let board = Board::decode("4;9E3;10E7;D6h").unwrap();
+        let wall_move = parse_move(&board, "d3h").unwrap();
+        let flipped = Diagram::new(
+            &board,
+            &DiagramOptions {
+                flipped: true,
+                arrow: Some(wall_move),
+                ..DiagramOptions::default()
+            },
+        );
+        let mut board_with_wall = board.clone();
+        board_with_wall.game_move(wall_move);
+        let flipped_with_wall = Diagram::new(
+            &board_with_wall,
+            &DiagramOptions {
+                flipped: true,
+                ..DiagramOptions::default()
+            },
+        );
+
+        let suggested = rects(&flipped, Color::ARROW);
+        assert_eq!(suggested.len(), 1);
+        assert!(rects(&flipped_with_wall, Color::WALL).contains(&suggested[0]));
+        assert!(flipped.to_svg().contains(&format!(
+            "fill=\"{}\" fill-opacity=\"{}\"",
+            Color::ARROW.hex(),
+            Color::ARROW.opacity()
+        )));
+    }
+}
@@ -0,0 +1,137 @@
+use dioxus::prelude::*;
+
+use quoridor::*;
+
+use quoridor_frontend::diagram::{Diagram, DiagramOptions};
+
+use crate::settings::use_locale;
+
+// The PNG is drawn this many times as large as the SVG, so it stays sharp on high resolution screens and in print.
+const PNG_SCALE: f32 = 2.0;
+
+// In the browser the file is downloaded through a link to its data, natively it's saved in `~/Downloads`.
+#[cfg(target_arch = "wasm32")]
+fn save_file(file_name: &str, mime: &str, bytes: &[u8]) {
+    use wasm_bindgen::{JsCast, JsValue};
+
+    let download = || -> Result<(), JsValue> {
+        let window = web_sys::window().ok_or("No window")?;
+        // `btoa` takes every character as one byte.
+        let base64 = window.btoa(&bytes.iter().map(|byte| *byte as char).collect::<String>())?;
+        let link: web_sys::HtmlAnchorElement = window
+            .document()
+            .ok_or("No document")?
+            .create_element("a")?
+            .dyn_into()
+            .map_err(JsValue::from)?;
+        link.set_href(&format!("data:{};base64,{}", mime, base64));
+        link.set_download(file_name);
+        link.click();
+        Ok(())
+    };
+    if let Err(err) = download() {
+        log::warn!("Could not download {}: {:?}", file_name, err);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_file(file_name: &str, _mime: &str, bytes: &[u8]) {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    let path = std::path::Path::new(&home).join("Downloads").join(file_name);
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, bytes));
+    match written {
+        Ok(()) => log::info!("Saved the diagram to {}", path.display()),
+        Err(err) => log::warn!("Could not save {}: {}", path.display(), err),
+    }
+}
+
+#[derive(Props, PartialEq)]
+pub struct DiagramExportProps {
+    /// The `Board::encode()` of the position.
+    encoding: String,
+    flipped: bool,
+    /// A move the diagram can point out, like the best move of the analysis.
+    #[props(!optional)]
+    suggestion: Option<Move>,
+}
+
+// Saves the position as a diagram for documentation or a forum post, with the options picked next to the buttons.
+pub fn DiagramExport(cx: Scope<DiagramExportProps>) -> Element {
+    let coordinates = use_state(cx, || true);
+    let caption = use_state(cx, || true);
+    let arrow = use_state(cx, || true);
+    let locale = use_locale(cx);
+
+    let encoding = &cx.props.encoding;
+    let turn = Board::decode(encoding).ok()?.turn;
+    // Only drawn when it's saved.
+    let diagram = move || {
+        let board = Board::decode(encoding).ok()?;
+        Some(Diagram::new(
+            &board,
+            &DiagramOptions {
+                flipped: cx.props.flipped,
+                coordinates: *coordinates.get(),
+                arrow: cx.props.suggestion.filter(|_| *arrow.get()),
+                caption: *caption.get(),
+            },
+        ))
+    };
+
+    cx.render(rsx! {
+        div { class: "flex flex-col space-y-2",
+            div { class: "text-xl", locale.text("export_diagram") },
+            label { class: "flex items-center space-x-2",
+                input {
+                    r#type: "checkbox",
+                    checked: *coordinates.get(),
+                    onclick: move |_| coordinates.set(!*coordinates.get()),
+                },
+                span { locale.text("coordinates") }
+            },
+            label { class: "flex items-center space-x-2",
+                input {
+                    r#type: "checkbox",
+                    checked: *caption.get(),
+                    onclick: move |_| caption.set(!*caption.get()),
+                },
+                span { locale.text("caption") }
+            },
+            if cx.props.suggestion.is_some() {
+                rsx! {
+                    label { class: "flex items-center space-x-2",
+                        input {
+                            r#type: "checkbox",
+                            checked: *arrow.get(),
+                            onclick: move |_| arrow.set(!*arrow.get()),
+                        },
+                        span { locale.text("show_best_move") }
+                    }
+                }
+            }
+            div { class: "flex space-x-2",
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| {
+                        if let Some(diagram) = diagram() {
+                            save_file(&format!("quoridor-turn-{}.svg", turn + 1), "image/svg+xml", diagram.to_svg().as_bytes());
+                        }
+                    },
+                    "SVG"
+                },
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| {
+                        if let Some(diagram) = diagram() {
+                            save_file(&format!("quoridor-turn-{}.png", turn + 1), "image/png", &diagram.to_png(PNG_SCALE));
+                        }
+                    },
+                    "PNG"
+                },
+            }
+        }
+    })
+}
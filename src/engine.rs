@@ -0,0 +1,1333 @@
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use quoridor::{AIControlledBoard, Board, MCNode, MirrorMoveType, MonteCarloTree, Move, PreCalc};
+
+use crate::analysis::{GameAnalysis, GameAnalyzer};
+use crate::game_result::{DrawRules, GameResult};
+use crate::grid::MoveHighlight;
+
+pub type BookError = Box<dyn std::error::Error + Sync + Send>;
+
+#[derive(Deserialize, Serialize, Debug)]
+pub enum UserCommand {
+    /// Start calculating from the position with this `Board::encode()` encoding.
+    DecodeBoard(String),
+    GameMove(Move),
+    SetAIPlayer(usize),
+    SetVariety(Variety),
+    /// Search the current position until the limit is reached, then send `CalculateUpdate::BestMove`.
+    Go(SearchLimit),
+    /// End the current `Go` search right away.
+    Stop,
+    /// The user gave up the game, playing this player, so the AI doesn't move anymore.
+    Resign(usize),
+    /// Search every position of the game with these moves from the position with encoding `start`, then send
+    /// `CalculateUpdate::Analysis`.
+    Analyze {
+        start: String,
+        moves: Vec<Move>,
+        visits: u32,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub enum SearchLimit {
+    Visits(u32),
+    MoveTime(u64),
+    Infinite,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub enum CalculateUpdate {
+    Finish(Move),
+    Progress(f32),
+    Info(SearchInfo),
+    /// The end of a `Go` search, every search sends one. `None` when there is no move, like on a finished position.
+    BestMove(Option<Move>),
+    /// Whether the AI is still following the book, sent every time the position changes.
+    Book(BookStatus),
+    AnalysisProgress(f32),
+    Analysis(GameAnalysis),
+    /// The AI gave up the game, it played this player.
+    Resign(usize),
+    /// How the search rated the position after `position` moves, just before the next move was made.
+    Evaluation {
+        position: usize,
+        win_rate_zero: f32,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum BookStatus {
+    /// The tree of the position comes from the book. `mirrored` when the book has the mirror image of the position.
+    InBook {
+        visits: u32,
+        win_rate_zero: f32,
+        mirrored: bool,
+    },
+    /// The book has a score for the position but no tree, the AI searches it itself.
+    InBookNoTree,
+    /// The position is in the book, but its tree could not be downloaded or didn't pass the checks.
+    DownloadFailed,
+    OutOfBook,
+}
+
+/// How a `Go` search is going. The win rate is for the player to move, the moves of the principal variation are in
+/// the orientation of the board the user sees.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SearchInfo {
+    pub visits: u32,
+    pub win_rate: f32,
+    pub principal_variation: Vec<Move>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SeenTableNew {
+    pub tabel: String,
+    pub win_rate: f64,
+    pub visits: u32,
+    pub ai_player: bool,
+}
+
+pub struct BoardWithHistory {
+    /// The position the moves were played from.
+    pub start: Board,
+    pub board: Board,
+    pub historic_moves: Vec<String>,
+    pub moves: Vec<Move>,
+    /// The chance white wins from the position after that many moves, if it's known.
+    pub evaluations: Vec<Option<f32>>,
+    /// Set once the game is over, the moves after that don't change it.
+    pub result: Option<GameResult>,
+}
+
+impl BoardWithHistory {
+    pub fn new() -> Self {
+        Self::from_position(Board::new())
+    }
+
+    pub fn from_position(board: Board) -> Self {
+        BoardWithHistory {
+            start: board.clone(),
+            board,
+            historic_moves: vec![],
+            moves: vec![],
+            evaluations: vec![],
+            result: None,
+        }
+    }
+
+    pub fn game_move(&mut self, game_move: Move) {
+        let quoridor_strats_move = game_move.to_quoridor_strat_notation(&self.board);
+        self.historic_moves.push(quoridor_strats_move);
+        self.moves.push(game_move);
+        self.board.game_move(game_move);
+        if self.result.is_none() {
+            self.result = DrawRules::default().result(&self.start, &self.moves);
+        }
+    }
+
+    /// Ends the game, `player` gives up.
+    pub fn resign(&mut self, player: usize) {
+        if self.result.is_none() {
+            self.result = Some(GameResult::Resigned { winner: 1 - player });
+        }
+    }
+
+    pub fn historic_moves(&self) -> String {
+        self.historic_moves.join(";")
+    }
+
+    pub fn set_evaluation(&mut self, position: usize, win_rate_zero: f32) {
+        if self.evaluations.len() <= position {
+            self.evaluations.resize(position + 1, None);
+        }
+        self.evaluations[position] = Some(win_rate_zero);
+    }
+
+    /// The last move of each side before `position`, the latest last.
+    pub fn last_moves(&self, position: usize) -> Vec<MoveHighlight> {
+        let first = position.saturating_sub(2);
+        let mut board = self.board_at(first);
+        let mut highlights = vec![];
+        for game_move in &self.moves[first..position.min(self.moves.len())] {
+            highlights.push(MoveHighlight::new(&board, *game_move));
+            board.game_move(*game_move);
+        }
+        highlights
+    }
+
+    /// The board after the first `position` moves.
+    pub fn board_at(&self, position: usize) -> Board {
+        let mut board = self.start.clone();
+        for game_move in self.moves.iter().take(position) {
+            board.game_move(*game_move);
+        }
+        board
+    }
+}
+
+impl Default for BoardWithHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Where the session sends its progress and moves, in the browser this is `postMessage` back to the main thread.
+#[allow(async_fn_in_trait)]
+pub trait UpdateSink {
+    fn send_update(&self, update: CalculateUpdate);
+
+    /// Called for positions outside of the book where the AI thinks it is losing, so they can be added to the book.
+    fn report_seen_table(&self, _seen_table: SeenTableNew, _historic_moves: &[Move]) {}
+}
+
+// For when the updates are handled by async code, like the Dioxus UI.
+impl UpdateSink for futures::channel::mpsc::UnboundedSender<CalculateUpdate> {
+    fn send_update(&self, update: CalculateUpdate) {
+        let _ = self.unbounded_send(update);
+    }
+}
+
+/// Where the session gets the moves of the user from.
+#[allow(async_fn_in_trait)]
+pub trait CommandSource {
+    /// Waits for the next command. Returns `None` when no more commands will come, which ends the session.
+    async fn recv(&mut self) -> Option<UserCommand>;
+
+    /// Called between search batches. Gives the other side the chance to send something and returns the next
+    /// command if there is one, without waiting for it.
+    async fn recv_or_yield(&mut self) -> Option<UserCommand>;
+}
+
+/// The opening book, `to_precalc.json` with the scores of all book positions and a tree per position.
+#[allow(async_fn_in_trait)]
+pub trait BookFetcher {
+    /// Shared, so sessions with the same book don't each need their own copy of the scores.
+    async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError>;
+
+    /// `None` when the book has a score for the position, but no tree.
+    async fn tree(&mut self, board: &Board) -> Result<Option<MonteCarloTree>, BookError>;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn now_millis() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_millis() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
+// Search batches are sized by how long they take, instead of a fixed number of steps. That way a slow phone doesn't
+// block new commands for seconds, and a fast desktop doesn't spend most of its time yielding.
+const BATCH_MILLIS: f64 = 50.0;
+// Directly after a command we do a short batch, so the progress indicator and AI move respond quickly.
+const FIRST_BATCH_MILLIS: f64 = 5.0;
+// How often a `Go` search reports how it is doing.
+const INFO_MILLIS: f64 = 500.0;
+// Below this the win rate of the root says too little to show it.
+const MIN_EVALUATION_VISITS: u32 = 1_000;
+
+struct BatchTimer {
+    steps_per_milli: f64,
+}
+
+impl BatchTimer {
+    fn new() -> Self {
+        BatchTimer {
+            steps_per_milli: 20.0,
+        }
+    }
+
+    fn number_of_steps(&self, millis: f64) -> u32 {
+        (self.steps_per_milli * millis).clamp(10.0, 100_000.0) as u32
+    }
+
+    fn record(&mut self, number_of_steps: u32, elapsed_millis: f64) {
+        let measured = number_of_steps as f64 / elapsed_millis.max(1.0);
+        // Smooth it out a bit, the cost of a step depends a lot on the position.
+        self.steps_per_milli = 0.7 * self.steps_per_milli + 0.3 * measured;
+    }
+}
+
+/// When the AI makes its move. The defaults are what the web worker uses.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct EngineConfig {
+    /// The AI moves once the root of the tree has more visits than this...
+    pub move_visits: u32,
+    /// ...or once it ran this many simulations for the move.
+    pub move_simulations: u64,
+    /// Stop searching altogether when the tree gets this big.
+    pub max_visits: u32,
+    /// Whether to use `to_precalc.json` and the precalculated trees.
+    pub use_book: bool,
+    /// The AI resigns when its win rate at the root of the tree is below this when it has to move...
+    pub resign_win_rate: f32,
+    /// ...for this many of its moves in a row, 0 never resigns...
+    pub resign_moves: usize,
+    /// ...and the root has at least this many visits, so the win rate can be trusted.
+    pub resign_visits: u32,
+    /// The web worker gets it from the settings with `UserCommand::SetVariety`.
+    pub variety: Variety,
+}
+
+/// How the AI picks its moves early in the game. Always playing the most visited move makes the AI play the same game
+/// every time the user plays the same moves, so in the opening it samples among the good moves instead.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Variety {
+    /// The AI samples its moves while the board has had fewer turns than this, 0 always plays the best move.
+    pub moves: usize,
+    /// A move is picked with a chance in proportion to its visits to the power `1 / temperature`. Lower picks the
+    /// most visited move more often, 1 follows the visits.
+    pub temperature: f32,
+    /// A move whose win rate is more than this below the one of the most visited move is never picked.
+    pub max_loss: f32,
+}
+
+impl Variety {
+    /// Always the strongest move, for matches and analysis.
+    pub const NONE: Variety = Variety {
+        moves: 0,
+        temperature: 1.0,
+        max_loss: 0.0,
+    };
+
+    /// For practice games, they diverge in the opening but the AI doesn't give away the game for it.
+    pub const PRACTICE: Variety = Variety {
+        moves: 10,
+        temperature: 1.0,
+        max_loss: 0.04,
+    };
+
+    // Whether the move after `turn` turns is sampled instead of the most visited one.
+    fn samples_at(&self, turn: usize) -> bool {
+        turn < self.moves && self.temperature > 0.0
+    }
+}
+
+impl Default for Variety {
+    fn default() -> Self {
+        Variety::NONE
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        EngineConfig {
+            move_visits: 600_000,
+            move_simulations: 300_000,
+            max_visits: 20_000_000,
+            use_book: true,
+            resign_win_rate: 0.03,
+            resign_moves: 3,
+            resign_visits: 300_000,
+            variety: Variety::default(),
+        }
+    }
+}
+
+/// The game the AI is playing against the user. It keeps the search tree, follows the book and decides when the AI
+/// has thought long enough to make its move. Everything platform specific goes through the three traits, so the same
+/// code runs in the web worker and natively.
+pub struct EngineSession<C, S, B> {
+    commands: C,
+    updates: S,
+    book: B,
+    pub ai_controlled_board: AIControlledBoard,
+    pub pre_calc: Rc<PreCalc>,
+    pub ai_player: Option<usize>,
+    pub config: EngineConfig,
+    // The calculation board is kept on one side of the symmetry axis, this says whether it is mirrored compared to the
+    // board the user sees. `None` as long as all moves were on the axis.
+    mirror_calc_board: Option<bool>,
+    pub historic_moves: Vec<Move>,
+    batch_timer: BatchTimer,
+    search: Option<Search>,
+    analysis: Option<GameAnalyzer>,
+    // How many moves in a row the AI has been below `EngineConfig::resign_win_rate`.
+    hopeless_moves: usize,
+    /// The player the AI played when it resigned, it doesn't move anymore after that.
+    pub resigned: Option<usize>,
+    rng: MoveRng,
+}
+
+// A xorshift generator. Picking among a few moves needs nothing better, and this way the engine needs no randomness
+// from the platform.
+struct MoveRng(u64);
+
+impl MoveRng {
+    fn new() -> MoveRng {
+        // Sessions that start in the same millisecond, like the engines of a match, still get their own moves.
+        static SESSIONS: AtomicU64 = AtomicU64::new(0);
+        let session = SESSIONS.fetch_add(1, Ordering::Relaxed);
+        MoveRng((now_millis() as u64 ^ session.wrapping_mul(0x9e37_79b9_7f4a_7c15)) | 1)
+    }
+
+    // Uniform in `0..1`.
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// The move the AI picks after `turn` turns from the root moves, given with their visits and win rate. `None` when it
+// plays the most visited move.
+fn sample_move(
+    variety: Variety,
+    turn: usize,
+    candidates: &[(Move, u32, f32)],
+    rng: &mut MoveRng,
+) -> Option<Move> {
+    if !variety.samples_at(turn) {
+        return None;
+    }
+    let &(_, _, best_win_rate) = candidates.iter().max_by_key(|(_, visits, _)| *visits)?;
+    let weighted: Vec<(Move, f64)> = candidates
+        .iter()
+        .filter(|(_, _, win_rate)| *win_rate >= best_win_rate - variety.max_loss)
+        .map(|(game_move, visits, _)| {
+            (
+                *game_move,
+                (*visits as f64).powf(1.0 / variety.temperature as f64),
+            )
+        })
+        .collect();
+    let mut pick = rng.next_f64() * weighted.iter().map(|(_, weight)| weight).sum::<f64>();
+    for (game_move, weight) in &weighted {
+        if pick < *weight {
+            return Some(*game_move);
+        }
+        pick -= weight;
+    }
+    None
+}
+
+// A running `Go` search.
+struct Search {
+    limit: SearchLimit,
+    started_at: f64,
+    // The visits of the root when the search started, from the book or an earlier search, don't count for its limit.
+    start_visits: u32,
+    last_info_at: f64,
+    suggested_move: Option<Move>,
+}
+
+impl Search {
+    fn is_finished(&self, number_visits: u32) -> bool {
+        match self.limit {
+            SearchLimit::Visits(visits) => {
+                number_visits.saturating_sub(self.start_visits) >= visits
+            }
+            SearchLimit::MoveTime(millis) => now_millis() - self.started_at >= millis as f64,
+            SearchLimit::Infinite => false,
+        }
+    }
+}
+
+/// Looks `board` up in the book. Returns the position as the book stores it, the chance player zero wins from it, and
+/// whether the book position is the mirror image of `board`.
+pub fn book_position(pre_calc: &PreCalc, board: &Board) -> Option<(Board, f32, bool)> {
+    let (score_zero, mirrored) = pre_calc.roll_out_score(board)?;
+    let book_board = if mirrored {
+        Board::decode(&board.encode_mirror()).unwrap()
+    } else {
+        board.clone()
+    };
+    Some((book_board, score_zero, mirrored))
+}
+
+/// Follows the most visited move from `mc_node` on, for at most `depth` moves.
+pub fn principal_variation(mc_node: &mut MCNode, depth: usize) -> Vec<Move> {
+    let mut moves = vec![];
+    let mut node = mc_node;
+    while moves.len() < depth {
+        let Some(move_options) = node.move_options() else {
+            break;
+        };
+        let Some((game_move, child, _)) = move_options
+            .iter_mut()
+            .max_by_key(|(_, child, _)| child.number_visits())
+        else {
+            break;
+        };
+        if child.number_visits() == 0 {
+            break;
+        }
+        moves.push(*game_move);
+        node = child;
+    }
+    moves
+}
+
+impl<C: CommandSource, S: UpdateSink, B: BookFetcher> EngineSession<C, S, B> {
+    pub async fn new(commands: C, updates: S, book: B) -> Self {
+        Self::with_config(commands, updates, book, EngineConfig::default()).await
+    }
+
+    pub async fn with_config(commands: C, updates: S, mut book: B, config: EngineConfig) -> Self {
+        let ai_controlled_board = AIControlledBoard::decode("0;10E1;10E9").unwrap();
+        let pre_calc = if !config.use_book {
+            Rc::new(PreCalc::new())
+        } else {
+            match book.board_scores().await {
+                Ok(pre_calc) => pre_calc,
+                Err(err) => {
+                    log::warn!("{}", err);
+                    Rc::new(PreCalc::new())
+                }
+            }
+        };
+        let mut session = EngineSession {
+            commands,
+            updates,
+            book,
+            ai_controlled_board,
+            pre_calc,
+            ai_player: None,
+            config,
+            mirror_calc_board: None,
+            historic_moves: vec![],
+            batch_timer: BatchTimer::new(),
+            search: None,
+            analysis: None,
+            hopeless_moves: 0,
+            resigned: None,
+            rng: MoveRng::new(),
+        };
+        session.follow_book().await;
+        session
+    }
+
+    /// Runs until the command source is closed and there is nothing left to calculate.
+    pub async fn run(mut self) {
+        loop {
+            let next_command = if self.is_done_calculating() {
+                self.finish_search();
+                // Nothing to calculate, so we sleep until the other side sends us something.
+                match self.commands.recv().await {
+                    Some(command) => Some(command),
+                    None => return,
+                }
+            } else {
+                self.commands.recv_or_yield().await
+            };
+            let new_command = next_command.is_some();
+            if let Some(next_command) = next_command {
+                self.handle_command(next_command).await;
+            }
+            // An analysis goes first, the user is waiting for it.
+            if self.analysis.is_some() {
+                self.analysis_batch();
+                continue;
+            }
+            if self.is_done_calculating() && !new_command {
+                continue;
+            }
+            self.search_batch(new_command).await;
+        }
+    }
+
+    fn is_done_calculating(&self) -> bool {
+        if self.analysis.is_some() {
+            return false;
+        }
+        let number_visits = self
+            .ai_controlled_board
+            .relevant_mc_tree
+            .mc_node
+            .number_visits();
+        self.ai_controlled_board.is_played_out() || number_visits >= self.config.max_visits
+    }
+
+    pub async fn handle_command(&mut self, command: UserCommand) {
+        match command {
+            UserCommand::DecodeBoard(encoding) => {
+                log::info!("Decoding board {}", encoding);
+                self.set_position(&encoding).await;
+            }
+            UserCommand::GameMove(game_move) => {
+                log::info!("Game Move {:?}", game_move);
+                self.search = None;
+                self.set_mirror_from(game_move);
+                self.historic_moves.push(game_move);
+                let game_move = if self.mirror_calc_board == Some(true) {
+                    game_move.mirror_move()
+                } else {
+                    game_move
+                };
+                self.take_game_move(game_move).await;
+            }
+            UserCommand::SetAIPlayer(player) => {
+                log::info!("Setting AI Player to {}", player);
+                self.ai_player = Some(player);
+            }
+            UserCommand::SetVariety(variety) => {
+                log::info!("Setting variety to {:?}", variety);
+                self.config.variety = variety;
+            }
+            UserCommand::Go(limit) => {
+                log::info!("Searching with limit {:?}", limit);
+                let start_visits = self
+                    .ai_controlled_board
+                    .relevant_mc_tree
+                    .mc_node
+                    .number_visits();
+                self.search = Some(Search {
+                    limit,
+                    started_at: now_millis(),
+                    start_visits,
+                    last_info_at: now_millis(),
+                    suggested_move: None,
+                });
+            }
+            UserCommand::Stop => {
+                self.finish_search();
+            }
+            UserCommand::Resign(player) => {
+                log::info!("User resigns as player {}", player);
+                self.ai_player = None;
+                self.search = None;
+            }
+            UserCommand::Analyze {
+                start,
+                moves,
+                visits,
+            } => {
+                let Ok(start) = Board::decode(&start) else {
+                    log::warn!("Could not decode board {}", start);
+                    return;
+                };
+                log::info!("Analyzing {} moves from {}", moves.len(), start.encode());
+                self.analysis = Some(GameAnalyzer::new(start, moves, visits));
+            }
+        }
+    }
+
+    async fn set_position(&mut self, encoding: &str) {
+        let Ok(ai_controlled_board) = AIControlledBoard::decode(encoding) else {
+            log::warn!("Could not decode board {}", encoding);
+            return;
+        };
+        self.ai_controlled_board = ai_controlled_board;
+        self.historic_moves = vec![];
+        self.search = None;
+        self.hopeless_moves = 0;
+        self.resigned = None;
+        // On a symmetric board we can still choose the side to calculate on, otherwise we start unmirrored.
+        let board = &self.ai_controlled_board.board;
+        self.mirror_calc_board = if board.encode() == board.encode_mirror() {
+            None
+        } else {
+            Some(false)
+        };
+        self.follow_book().await;
+    }
+
+    fn to_user_orientation(&self, game_move: Move) -> Move {
+        if self.mirror_calc_board == Some(true) {
+            game_move.mirror_move()
+        } else {
+            game_move
+        }
+    }
+
+    fn send_search_info(&mut self) {
+        let mc_node = &mut self.ai_controlled_board.relevant_mc_tree.mc_node;
+        let score = mc_node.scores();
+        // The scores are from the perspective of the player who made the last move.
+        let win_rate = 1.0 - score.0 as f32 / score.1.max(1) as f32;
+        let principal_variation = principal_variation(mc_node, 10)
+            .into_iter()
+            .map(|game_move| self.to_user_orientation(game_move))
+            .collect();
+        self.updates.send_update(CalculateUpdate::Info(SearchInfo {
+            visits: score.1,
+            win_rate,
+            principal_variation,
+        }));
+    }
+
+    // Ends the `Go` search if there is one, and reports the move it found.
+    fn finish_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        self.send_search_info();
+        let suggested_move = search.suggested_move.or_else(|| {
+            principal_variation(&mut self.ai_controlled_board.relevant_mc_tree.mc_node, 1)
+                .first()
+                .copied()
+        });
+        if suggested_move.is_none() {
+            log::warn!("Search ended without a move");
+        }
+        let best_move = suggested_move.map(|game_move| self.to_user_orientation(game_move));
+        self.updates
+            .send_update(CalculateUpdate::BestMove(best_move));
+    }
+
+    // The first move that is not on the symmetry axis decides on which side we keep the calculation board.
+    fn set_mirror_from(&mut self, game_move: Move) {
+        if self.mirror_calc_board.is_none() {
+            match game_move.mirror_move_type() {
+                MirrorMoveType::Right => {
+                    self.mirror_calc_board = Some(true);
+                }
+                MirrorMoveType::Left => {
+                    self.mirror_calc_board = Some(false);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Runs one batch of the search, and makes the AI move if it has thought long enough. Returns the move the AI
+    /// made, if it made one.
+    pub async fn search_batch(&mut self, new_command: bool) -> Option<Move> {
+        let number_visits = self
+            .ai_controlled_board
+            .relevant_mc_tree
+            .mc_node
+            .number_visits();
+        let number_of_steps = if new_command {
+            self.batch_timer.number_of_steps(FIRST_BATCH_MILLIS)
+        } else {
+            self.batch_timer.number_of_steps(BATCH_MILLIS)
+        };
+
+        let batch_start = now_millis();
+        let resp = self
+            .ai_controlled_board
+            .ai_move(number_of_steps as _, &self.pre_calc);
+        self.batch_timer
+            .record(number_of_steps, now_millis() - batch_start);
+        //log::info!("AI Move: {:?}", resp);
+
+        if let Some(search) = &mut self.search {
+            search.suggested_move = Some(resp.suggested_move);
+            let report = now_millis() - search.last_info_at >= INFO_MILLIS;
+            if report {
+                search.last_info_at = now_millis();
+            }
+            let finished = search.is_finished(number_visits);
+            if finished {
+                self.finish_search();
+            } else if report {
+                self.send_search_info();
+            }
+            return None;
+        }
+
+        if number_visits > self.config.move_visits
+            || self.ai_controlled_board.is_played_out()
+            || resp.number_of_simulations >= self.config.move_simulations
+        {
+            if self.ai_player == Some(self.ai_controlled_board.board.turn % 2) {
+                if self.should_resign() {
+                    let player = self.ai_controlled_board.board.turn % 2;
+                    log::info!("AI resigns as player {}", player);
+                    self.resigned = Some(player);
+                    self.ai_player = None;
+                    self.updates.send_update(CalculateUpdate::Resign(player));
+                    return None;
+                }
+                let game_move = self.pick_move(resp.suggested_move);
+                log::info!("AI TOOK MOVE IN WORKER Move: {:?}", game_move);
+                self.set_mirror_from(game_move);
+                let to_send = self.to_user_orientation(game_move);
+                self.historic_moves.push(to_send);
+                self.updates.send_update(CalculateUpdate::Finish(to_send));
+                self.take_game_move(game_move).await;
+                return Some(to_send);
+            }
+        } else {
+            self.updates.send_update(CalculateUpdate::Progress(
+                number_visits as f32 / self.config.move_visits as f32,
+            ));
+        }
+        None
+    }
+
+    // The move the AI makes, `suggested_move` or early in the game one of the root moves that is almost as good.
+    fn pick_move(&mut self, suggested_move: Move) -> Move {
+        let variety = self.config.variety;
+        let turn = self.ai_controlled_board.board.turn;
+        if !variety.samples_at(turn) {
+            return suggested_move;
+        }
+        let Some(move_options) = self
+            .ai_controlled_board
+            .relevant_mc_tree
+            .mc_node
+            .move_options()
+        else {
+            return suggested_move;
+        };
+        // The children are after the move of the AI, so their scores are from its perspective.
+        let candidates: Vec<(Move, u32, f32)> = move_options
+            .iter()
+            .filter(|(_, child, _)| child.number_visits() > 0)
+            .map(|(game_move, child, _)| {
+                let score = child.scores();
+                (*game_move, score.1, score.0 as f32 / score.1.max(1) as f32)
+            })
+            .collect();
+        sample_move(variety, turn, &candidates, &mut self.rng).unwrap_or(suggested_move)
+    }
+
+    // Called once per move of the AI, when it would make it.
+    fn should_resign(&mut self) -> bool {
+        let score = self.ai_controlled_board.relevant_mc_tree.mc_node.scores();
+        // The scores are from the perspective of the player who made the last move, the opponent of the AI.
+        let win_rate_ai = 1.0 - score.0 as f32 / score.1.max(1) as f32;
+        if score.1 >= self.config.resign_visits && win_rate_ai < self.config.resign_win_rate {
+            self.hopeless_moves += 1;
+        } else {
+            self.hopeless_moves = 0;
+        }
+        self.config.resign_moves > 0 && self.hopeless_moves >= self.config.resign_moves
+    }
+
+    fn analysis_batch(&mut self) {
+        let Some(analysis) = &mut self.analysis else {
+            return;
+        };
+        let number_of_steps = self.batch_timer.number_of_steps(BATCH_MILLIS);
+        let batch_start = now_millis();
+        analysis.step(number_of_steps as _, &self.pre_calc);
+        self.batch_timer
+            .record(number_of_steps, now_millis() - batch_start);
+        if analysis.is_done() {
+            let analysis = self.analysis.take().unwrap().finish();
+            self.updates
+                .send_update(CalculateUpdate::Analysis(analysis));
+        } else {
+            self.updates
+                .send_update(CalculateUpdate::AnalysisProgress(analysis.progress()));
+        }
+    }
+
+    // Reports what the search thinks of the position the move is made in, the move is already in `historic_moves`.
+    fn send_evaluation(&self) {
+        let score = self.ai_controlled_board.relevant_mc_tree.mc_node.scores();
+        if score.1 < MIN_EVALUATION_VISITS || self.historic_moves.is_empty() {
+            return;
+        }
+        // The scores are from the perspective of the player who made the last move.
+        let win_rate_last_move = score.0 as f32 / score.1 as f32;
+        let win_rate_zero = if self.ai_controlled_board.board.turn % 2 == 0 {
+            1.0 - win_rate_last_move
+        } else {
+            win_rate_last_move
+        };
+        self.updates.send_update(CalculateUpdate::Evaluation {
+            position: self.historic_moves.len() - 1,
+            win_rate_zero,
+        });
+    }
+
+    async fn take_game_move(&mut self, game_move: Move) {
+        self.send_evaluation();
+        self.ai_controlled_board.game_move(game_move);
+        if !self.follow_book().await {
+            self.store_table_if_unknown_and_ai_loses();
+        }
+    }
+
+    // Swaps in the precalculated tree if the position is in the book. Returns whether the position is in the book.
+    async fn follow_book(&mut self) -> bool {
+        if let Some((to_download, score_zero, pre_calc_mirrored)) =
+            book_position(&self.pre_calc, &self.ai_controlled_board.board)
+        {
+            match self.book.tree(&to_download).await {
+                Ok(Some(mc_tree)) => {
+                    log::info!(
+                        "Found precalc {} with {} visits, is precalc mirror {}",
+                        to_download.encode(),
+                        mc_tree.mc_node.number_visits(),
+                        pre_calc_mirrored
+                    );
+                    self.updates
+                        .send_update(CalculateUpdate::Book(BookStatus::InBook {
+                            visits: mc_tree.mc_node.number_visits(),
+                            win_rate_zero: score_zero,
+                            mirrored: pre_calc_mirrored,
+                        }));
+                    self.ai_controlled_board.relevant_mc_tree = mc_tree;
+                    self.ai_controlled_board.board = to_download;
+                    self.mirror_calc_board = match (self.mirror_calc_board, pre_calc_mirrored) {
+                        (Some(value), true) => Some(!value),
+                        (Some(value), false) => Some(value),
+                        (None, _) => None,
+                    };
+                }
+                Ok(None) => {
+                    log::info!("The book has no tree of {}", to_download.encode());
+                    self.updates
+                        .send_update(CalculateUpdate::Book(BookStatus::InBookNoTree));
+                }
+                // Searching from scratch is slower, but still better than searching on a tree that is broken.
+                Err(err) => {
+                    log::warn!(
+                        "Not using the book tree of {}, searching without it: {}",
+                        to_download.encode(),
+                        err
+                    );
+                    self.updates
+                        .send_update(CalculateUpdate::Book(BookStatus::DownloadFailed));
+                }
+            }
+            true
+        } else {
+            self.updates
+                .send_update(CalculateUpdate::Book(BookStatus::OutOfBook));
+            false
+        }
+    }
+
+    fn store_table_if_unknown_and_ai_loses(&self) {
+        let Some(ai_player) = self.ai_player else {
+            return;
+        };
+        let score = self.ai_controlled_board.relevant_mc_tree.mc_node.scores();
+        let win_rate_prev_player = score.0 as f64 / score.1 as f64;
+        let win_rate_ai = if ai_player == self.ai_controlled_board.board.turn % 2 {
+            1.0 - win_rate_prev_player
+        } else {
+            win_rate_prev_player
+        };
+        if win_rate_ai < 0.4 && score.1 > 300_000 {
+            self.updates.report_seen_table(
+                SeenTableNew {
+                    tabel: self.ai_controlled_board.board.encode(),
+                    win_rate: win_rate_ai,
+                    visits: score.1,
+                    ai_player: ai_player == 0,
+                },
+                &self.historic_moves,
+            );
+        }
+    }
+}
+
+// Stand-ins for running a session without a browser, for example from `cargo test`.
+
+/// Replays a fixed list of commands, and closes once they are all used.
+pub struct VecCommands {
+    pub commands: std::collections::VecDeque<UserCommand>,
+}
+
+impl CommandSource for VecCommands {
+    async fn recv(&mut self) -> Option<UserCommand> {
+        self.commands.pop_front()
+    }
+
+    async fn recv_or_yield(&mut self) -> Option<UserCommand> {
+        self.commands.pop_front()
+    }
+}
+
+/// Throws all updates away, for when the caller only needs the moves `search_batch` returns.
+pub struct DiscardUpdates;
+
+impl UpdateSink for DiscardUpdates {
+    fn send_update(&self, _update: CalculateUpdate) {}
+}
+
+/// Keeps every update, so they can be inspected afterwards.
+#[derive(Default)]
+pub struct CollectUpdates {
+    pub updates: std::cell::RefCell<Vec<CalculateUpdate>>,
+}
+
+impl UpdateSink for CollectUpdates {
+    fn send_update(&self, update: CalculateUpdate) {
+        self.updates.borrow_mut().push(update);
+    }
+}
+
+/// A book kept in memory, the trees are stored serialized by `Board::encode()`, like the files on the server.
+#[derive(Default)]
+pub struct MemoryBook {
+    pub scores: Option<PreCalc>,
+    pub trees: std::collections::HashMap<String, Vec<u8>>,
+}
+
+impl BookFetcher for MemoryBook {
+    async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError> {
+        Ok(Rc::new(self.scores.take().unwrap_or_default()))
+    }
+
+    async fn tree(&mut self, board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+        Ok(self
+            .trees
+            .get(&board.encode())
+            .map(|bytes| MonteCarloTree::deserialize(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use futures::executor::block_on;
+    use quoridor::PawnMove;
+
+    use super::*;
+
+    const START: &str = "0;10E1;10E9";
+
+    // Small enough that the AI moves after a few batches.
+    fn quick_config() -> EngineConfig {
+        EngineConfig {
+            move_visits: 2_000,
+            move_simulations: 2_000,
+            use_book: false,
+            ..EngineConfig::default()
+        }
+    }
+
+    fn new_session<B: BookFetcher>(
+        book: B,
+        config: EngineConfig,
+    ) -> EngineSession<VecCommands, CollectUpdates, B> {
+        block_on(EngineSession::with_config(
+            VecCommands {
+                commands: VecDeque::new(),
+            },
+            CollectUpdates::default(),
+            book,
+            config,
+        ))
+    }
+
+    // Runs batches until the AI moves, or gives up after `batches`.
+    fn ai_move_within<C: CommandSource, S: UpdateSink, B: BookFetcher>(
+        session: &mut EngineSession<C, S, B>,
+        batches: usize,
+    ) -> Option<Move> {
+        (0..batches).find_map(|batch| block_on(session.search_batch(batch == 0)))
+    }
+
+    #[test]
+    fn a_move_right_of_the_axis_mirrors_the_calculation_board() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        let game_move = Move::PawnMove(PawnMove::Right, None);
+        block_on(session.handle_command(UserCommand::GameMove(game_move)));
+
+        assert_eq!(session.mirror_calc_board, Some(true));
+        // The user sees the move as it was made, the engine calculates on the left side.
+        assert_eq!(session.historic_moves, vec![game_move]);
+        let mut mirrored = Board::new();
+        mirrored.game_move(game_move.mirror_move());
+        assert_eq!(
+            session.ai_controlled_board.board.encode(),
+            mirrored.encode()
+        );
+    }
+
+    #[test]
+    fn the_ai_move_is_sent_in_the_orientation_of_the_user() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        block_on(session.handle_command(UserCommand::SetAIPlayer(1)));
+        block_on(
+            session.handle_command(UserCommand::GameMove(Move::PawnMove(PawnMove::Right, None))),
+        );
+
+        let ai_move = ai_move_within(&mut session, 1_000).expect("the AI should move");
+        let finished: Vec<Move> = session
+            .updates
+            .updates
+            .borrow()
+            .iter()
+            .filter_map(|update| match update {
+                CalculateUpdate::Finish(game_move) => Some(*game_move),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(finished, vec![ai_move]);
+        assert_eq!(session.historic_moves.last(), Some(&ai_move));
+        // Replaying the moves as the user saw them gives the mirror image of the calculation board.
+        let mut user_board = Board::new();
+        for game_move in &session.historic_moves {
+            user_board.game_move(*game_move);
+        }
+        assert_eq!(
+            user_board.encode_mirror(),
+            session.ai_controlled_board.board.encode()
+        );
+    }
+
+    #[test]
+    fn a_position_in_the_book_uses_its_tree() {
+        let mut searched = AIControlledBoard::decode(START).unwrap();
+        searched.ai_move(500, &PreCalc::default());
+        let visits = searched.relevant_mc_tree.mc_node.number_visits();
+        let scores = serde_json::from_value(serde_json::json!({
+            "scores_player_zero": { Board::new().encode(): 0.55 }
+        }))
+        .unwrap();
+        let book = MemoryBook {
+            scores: Some(scores),
+            trees: [(Board::new().encode(), searched.relevant_mc_tree.serialize())]
+                .into_iter()
+                .collect(),
+        };
+
+        let session = new_session(
+            book,
+            EngineConfig {
+                use_book: true,
+                ..quick_config()
+            },
+        );
+
+        assert_eq!(
+            session
+                .ai_controlled_board
+                .relevant_mc_tree
+                .mc_node
+                .number_visits(),
+            visits
+        );
+        assert!(session.updates.updates.borrow().iter().any(|update| matches!(
+            update,
+            CalculateUpdate::Book(BookStatus::InBook { visits: book_visits, .. }) if *book_visits == visits
+        )));
+    }
+
+    #[test]
+    fn a_book_position_without_a_tree_is_not_a_failure() {
+        let scores = serde_json::from_value(serde_json::json!({
+            "scores_player_zero": { Board::new().encode(): 0.55 }
+        }))
+        .unwrap();
+        let book = MemoryBook {
+            scores: Some(scores),
+            trees: Default::default(),
+        };
+
+        let session = new_session(
+            book,
+            EngineConfig {
+                use_book: true,
+                ..quick_config()
+            },
+        );
+
+        assert!(matches!(
+            session.updates.updates.borrow().last(),
+            Some(CalculateUpdate::Book(BookStatus::InBookNoTree))
+        ));
+    }
+
+    // A book whose trees can never be fetched.
+    struct UnreachableBook;
+
+    impl BookFetcher for UnreachableBook {
+        async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError> {
+            Ok(Rc::new(
+                serde_json::from_value(serde_json::json!({
+                    "scores_player_zero": { Board::new().encode(): 0.55 }
+                }))
+                .unwrap(),
+            ))
+        }
+
+        async fn tree(&mut self, _board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+            Err("the server is gone")?
+        }
+    }
+
+    #[test]
+    fn a_book_tree_that_can_not_be_fetched_is_reported() {
+        let session = new_session(
+            UnreachableBook,
+            EngineConfig {
+                use_book: true,
+                ..quick_config()
+            },
+        );
+
+        assert!(matches!(
+            session.updates.updates.borrow().last(),
+            Some(CalculateUpdate::Book(BookStatus::DownloadFailed))
+        ));
+    }
+
+    #[test]
+    fn the_ai_waits_for_enough_visits() {
+        // Only the visits can make the AI move.
+        let config = EngineConfig {
+            move_simulations: u64::MAX,
+            ..quick_config()
+        };
+        let mut session = new_session(MemoryBook::default(), config);
+        block_on(session.handle_command(UserCommand::SetAIPlayer(0)));
+
+        let mut batches = 0;
+        let ai_move = loop {
+            let visits = session
+                .ai_controlled_board
+                .relevant_mc_tree
+                .mc_node
+                .number_visits();
+            if let Some(game_move) = block_on(session.search_batch(batches == 0)) {
+                assert!(visits > config.move_visits);
+                break game_move;
+            }
+            batches += 1;
+            assert!(batches < 1_000, "the AI should move");
+        };
+        assert_eq!(session.historic_moves, vec![ai_move]);
+        assert_eq!(session.ai_controlled_board.board.turn, 1);
+    }
+
+    fn best_moves(
+        session: &EngineSession<VecCommands, CollectUpdates, MemoryBook>,
+    ) -> Vec<Option<Move>> {
+        session
+            .updates
+            .updates
+            .borrow()
+            .iter()
+            .filter_map(|update| match update {
+                CalculateUpdate::BestMove(best_move) => Some(*best_move),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_search_without_a_move_still_ends_with_a_best_move() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        block_on(session.handle_command(UserCommand::Go(SearchLimit::Infinite)));
+        block_on(session.handle_command(UserCommand::Stop));
+
+        assert_eq!(best_moves(&session), vec![None]);
+    }
+
+    #[test]
+    fn the_visits_of_a_search_count_from_its_start() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        // Pondering before the search fills the tree.
+        for batch in 0..5 {
+            block_on(session.search_batch(batch == 0));
+        }
+        let visits = session
+            .ai_controlled_board
+            .relevant_mc_tree
+            .mc_node
+            .number_visits();
+        assert!(visits > 0);
+        block_on(session.handle_command(UserCommand::Go(SearchLimit::Visits(visits))));
+        block_on(session.search_batch(true));
+
+        assert!(best_moves(&session).is_empty());
+    }
+
+    #[test]
+    fn the_ai_does_not_move_for_the_user() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        block_on(session.handle_command(UserCommand::SetAIPlayer(1)));
+
+        assert_eq!(ai_move_within(&mut session, 50), None);
+        assert!(session.historic_moves.is_empty());
+        assert_eq!(session.ai_controlled_board.board.turn, 0);
+    }
+
+    #[test]
+    fn the_ai_does_not_move_after_the_user_resigned() {
+        let mut session = new_session(MemoryBook::default(), quick_config());
+        block_on(session.handle_command(UserCommand::SetAIPlayer(0)));
+        block_on(session.handle_command(UserCommand::Resign(1)));
+
+        assert_eq!(ai_move_within(&mut session, 50), None);
+        assert_eq!(session.ai_player, None);
+        assert!(session.historic_moves.is_empty());
+    }
+
+    fn pawn_move(direction: PawnMove) -> Move {
+        Move::PawnMove(direction, None)
+    }
+
+    // The most visited move first, the last one is far worse than the others.
+    fn root_moves() -> Vec<(Move, u32, f32)> {
+        vec![
+            (pawn_move(PawnMove::Up), 5_000, 0.60),
+            (pawn_move(PawnMove::Left), 3_000, 0.58),
+            (pawn_move(PawnMove::Right), 2_000, 0.62),
+            (pawn_move(PawnMove::Down), 4_000, 0.40),
+        ]
+    }
+
+    #[test]
+    fn without_variety_the_most_visited_move_is_played() {
+        let mut rng = MoveRng(0x2545_f491_4f6c_dd1d);
+        for turn in 0..20 {
+            assert_eq!(
+                sample_move(Variety::NONE, turn, &root_moves(), &mut rng),
+                None
+            );
+        }
+        let cold = Variety {
+            temperature: 0.0,
+            ..Variety::PRACTICE
+        };
+        assert_eq!(sample_move(cold, 0, &root_moves(), &mut rng), None);
+    }
+
+    #[test]
+    fn moves_losing_more_than_max_loss_are_never_sampled() {
+        let mut rng = MoveRng(0x2545_f491_4f6c_dd1d);
+        let mut picked = vec![];
+        for _ in 0..2_000 {
+            let game_move = sample_move(Variety::PRACTICE, 0, &root_moves(), &mut rng).unwrap();
+            if !picked.contains(&game_move) {
+                picked.push(game_move);
+            }
+        }
+        assert!(!picked.contains(&pawn_move(PawnMove::Down)));
+        // Every move within `max_loss` does come up, also the ones with fewer visits.
+        assert_eq!(picked.len(), 3);
+    }
+
+    #[test]
+    fn only_the_opening_moves_are_sampled() {
+        let mut rng = MoveRng(0x2545_f491_4f6c_dd1d);
+        let turns = Variety::PRACTICE.moves;
+        assert!(sample_move(Variety::PRACTICE, turns - 1, &root_moves(), &mut rng).is_some());
+        assert_eq!(
+            sample_move(Variety::PRACTICE, turns, &root_moves(), &mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn a_lower_temperature_favours_the_most_visited_move() {
+        let most_visited = |temperature: f32| {
+            let variety = Variety {
+                temperature,
+                ..Variety::PRACTICE
+            };
+            let mut rng = MoveRng(0x2545_f491_4f6c_dd1d);
+            (0..2_000)
+                .filter(|_| {
+                    sample_move(variety, 0, &root_moves(), &mut rng)
+                        == Some(pawn_move(PawnMove::Up))
+                })
+                .count()
+        };
+        assert!(most_visited(0.25) > most_visited(1.0));
+    }
+}
@@ -0,0 +1,61 @@
+use quoridor::{Board, MonteCarloTree, Move, PreCalc};
+
+use crate::engine::book_position;
+use crate::text_board::legal_moves;
+
+/// A move from an explored position that leads to another position in the book.
+#[derive(Clone, Debug)]
+pub struct BookContinuation {
+    pub game_move: Move,
+    pub notation: String,
+    /// The chance player zero wins after this move, from `scores_player_zero`.
+    pub win_rate_zero: f32,
+    /// How often the move was visited in the tree of the explored position, if that tree is in the book.
+    pub visits: Option<u32>,
+    /// The book only has the mirror image of the position after this move.
+    pub mirrored: bool,
+}
+
+/// All moves from `board` that stay in the book, most visited first. `tree` is the book tree of `board` and whether
+/// it is stored mirrored, as `book_position` finds it.
+pub fn book_continuations(
+    board: &Board,
+    pre_calc: &PreCalc,
+    tree: Option<(MonteCarloTree, bool)>,
+) -> Vec<BookContinuation> {
+    let mut tree_visits = vec![];
+    if let Some((mut tree, tree_mirrored)) = tree {
+        if let Some(move_options) = tree.mc_node.move_options() {
+            for (game_move, child, _) in move_options.iter() {
+                let game_move = if tree_mirrored {
+                    game_move.mirror_move()
+                } else {
+                    *game_move
+                };
+                tree_visits.push((game_move, child.number_visits()));
+            }
+        }
+    }
+
+    let mut continuations = vec![];
+    for game_move in legal_moves(board) {
+        let mut next = board.clone();
+        next.game_move(game_move);
+        let Some((_, win_rate_zero, mirrored)) = book_position(pre_calc, &next) else {
+            continue;
+        };
+        let visits = tree_visits
+            .iter()
+            .find(|(tree_move, _)| *tree_move == game_move)
+            .map(|(_, visits)| *visits);
+        continuations.push(BookContinuation {
+            game_move,
+            notation: game_move.to_quoridor_strat_notation(board),
+            win_rate_zero,
+            visits,
+            mirrored,
+        });
+    }
+    continuations.sort_by(|a, b| b.visits.cmp(&a.visits).then(a.notation.cmp(&b.notation)));
+    continuations
+}
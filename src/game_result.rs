@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use quoridor::{Board, Move};
+
+use crate::grid::winner;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    /// The game took `DrawRules::max_moves` moves.
+    MoveLimit,
+    /// The same position came back `DrawRules::repetitions` times, with the same player to move.
+    Repetition,
+}
+
+/// How a game ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum GameResult {
+    /// The pawn of the winner reached its goal row.
+    Goal {
+        winner: usize,
+    },
+    Resigned {
+        winner: usize,
+    },
+    Draw(DrawReason),
+}
+
+impl GameResult {
+    pub fn winner(&self) -> Option<usize> {
+        match self {
+            GameResult::Goal { winner } | GameResult::Resigned { winner } => Some(*winner),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    /// Like in chess, `1-0` when white wins.
+    pub fn score(&self) -> &'static str {
+        match self.winner() {
+            Some(0) => "1-0",
+            Some(_) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+/// When a game that nobody can or wants to win is over. Without them two engines can move their pawns back and forth
+/// forever once the walls are used up.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DrawRules {
+    pub max_moves: usize,
+    pub repetitions: usize,
+}
+
+impl Default for DrawRules {
+    fn default() -> Self {
+        DrawRules {
+            max_moves: 200,
+            repetitions: 3,
+        }
+    }
+}
+
+// What makes two positions the same for repetitions: the encoding without its turn number, which always differs, and
+// the player to move.
+fn position_key(board: &Board) -> (String, usize) {
+    let encoding = board.encode();
+    let without_turn = encoding
+        .split_once(';')
+        .map_or(encoding.as_str(), |(_, rest)| rest)
+        .to_string();
+    (without_turn, board.turn % 2)
+}
+
+impl DrawRules {
+    /// The result of the game with these moves from `start`, if it's over. Resigning is up to the players, so it's
+    /// never the result here.
+    pub fn result(&self, start: &Board, moves: &[Move]) -> Option<GameResult> {
+        let mut board = start.clone();
+        let mut seen: HashMap<(String, usize), usize> = HashMap::new();
+        *seen.entry(position_key(&board)).or_default() += 1;
+        for game_move in moves {
+            board.game_move(*game_move);
+            *seen.entry(position_key(&board)).or_default() += 1;
+        }
+        if let Some(winner) = winner(&board) {
+            return Some(GameResult::Goal { winner });
+        }
+        if seen.get(&position_key(&board)).copied().unwrap_or(0) >= self.repetitions {
+            return Some(GameResult::Draw(DrawReason::Repetition));
+        }
+        if moves.len() >= self.max_moves {
+            return Some(GameResult::Draw(DrawReason::MoveLimit));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::text_board::parse_move;
+
+    // Plays the moves in notation from the start.
+    fn moves(notations: &[&str]) -> Vec<Move> {
+        let mut board = Board::new();
+        notations
+            .iter()
+            .map(|notation| {
+                let game_move = parse_move(&board, notation).unwrap();
+                board.game_move(game_move);
+                game_move
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pawns_shuffling_back_and_forth_is_a_draw_by_repetition() {
+        let rules = DrawRules::default();
+        // After every four moves the pawns are back where they started, with white to move again.
+        let shuffle = ["e2", "e8", "e1", "e9"];
+        let twice: Vec<&str> = shuffle.iter().chain(&shuffle).copied().collect();
+        assert_eq!(
+            rules.result(&Board::new(), &moves(&twice)),
+            Some(GameResult::Draw(DrawReason::Repetition))
+        );
+        assert_eq!(rules.result(&Board::new(), &moves(&twice[..7])), None);
+    }
+
+    #[test]
+    fn a_long_game_is_a_draw_by_the_move_limit() {
+        let rules = DrawRules {
+            max_moves: 6,
+            repetitions: 10,
+        };
+        let moves = moves(&["e2", "e8", "e1", "e9", "e2", "e8"]);
+        assert_eq!(
+            rules.result(&Board::new(), &moves),
+            Some(GameResult::Draw(DrawReason::MoveLimit))
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use quoridor::*;
+
+pub const DIMENSION: usize = 9;
+
+/// The walls a groove square of the 17x17 display grid can be part of.
+pub fn part_of_walls(
+    square_type: SquareType,
+    row: usize,
+    col: usize,
+) -> Vec<(WallDirection, Position)> {
+    let row = row as i8;
+    let col = col as i8;
+    let mut walls = vec![];
+    match square_type {
+        SquareType::VerticalBorder => {
+            if col / 2 >= 8 {
+                return vec![];
+            }
+            if row / 2 >= 1 {
+                walls.push((
+                    WallDirection::Vertical,
+                    Position {
+                        row: row / 2 - 1,
+                        col: col / 2,
+                    },
+                ));
+            };
+            if row / 2 < 8 {
+                walls.push((
+                    WallDirection::Vertical,
+                    Position {
+                        row: row / 2,
+                        col: col / 2,
+                    },
+                ));
+            };
+        }
+        SquareType::HorizontalBorder => {
+            if row / 2 >= 8 {
+                return vec![];
+            }
+            if col / 2 >= 1 {
+                walls.push((
+                    WallDirection::Horizontal,
+                    Position {
+                        row: row / 2,
+                        col: col / 2 - 1,
+                    },
+                ));
+            }
+            if col / 2 < 8 {
+                walls.push((
+                    WallDirection::Horizontal,
+                    Position {
+                        row: row / 2,
+                        col: col / 2,
+                    },
+                ));
+            };
+        }
+        SquareType::Corner => {
+            walls.push((
+                WallDirection::Horizontal,
+                Position {
+                    row: row / 2,
+                    col: col / 2,
+                },
+            ));
+            walls.push((
+                WallDirection::Vertical,
+                Position {
+                    row: row / 2,
+                    col: col / 2,
+                },
+            ));
+        }
+        _ => (),
+    };
+    walls
+}
+
+pub fn is_part_of_wall(board: &Board, square_type: SquareType, row: usize, col: usize) -> bool {
+    let walls = part_of_walls(square_type, row, col);
+    for wall in walls {
+        if board.walls.is_allowed(wall.0, wall.1) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SquareType {
+    Square,
+    VerticalBorder,
+    HorizontalBorder,
+    Corner,
+}
+
+impl SquareType {
+    /// The type of a square in the 17x17 display grid, where the odd rows and columns are the grooves between squares.
+    pub fn at(row: usize, col: usize) -> SquareType {
+        match (row % 2 == 0, col % 2 == 0) {
+            (true, true) => SquareType::Square,
+            (true, false) => SquareType::VerticalBorder,
+            (false, true) => SquareType::HorizontalBorder,
+            (false, false) => SquareType::Corner,
+        }
+    }
+}
+
+/// What a move changed on the board, so it can be pointed out.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MoveHighlight {
+    Pawn { from: Position, to: Position },
+    Wall(WallDirection, Position),
+}
+
+impl MoveHighlight {
+    /// `board` is the position the move is made in.
+    pub fn new(board: &Board, game_move: Move) -> MoveHighlight {
+        match game_move {
+            Move::Wall(direction, position) => MoveHighlight::Wall(direction, position),
+            Move::PawnMove(..) => {
+                let player = board.turn % 2;
+                let mut after = board.clone();
+                after.game_move(game_move);
+                MoveHighlight::Pawn {
+                    from: board.pawns[player].position,
+                    to: after.pawns[player].position,
+                }
+            }
+        }
+    }
+
+    /// Whether the pawn moved away from this square of the board.
+    pub fn is_origin(&self, row: usize, col: usize) -> bool {
+        matches!(self, MoveHighlight::Pawn { from, .. } if from.row as usize == row && from.col as usize == col)
+    }
+
+    pub fn is_destination(&self, row: usize, col: usize) -> bool {
+        matches!(self, MoveHighlight::Pawn { to, .. } if to.row as usize == row && to.col as usize == col)
+    }
+
+    pub fn is_wall_square(&self, square_type: SquareType, row: usize, col: usize) -> bool {
+        match self {
+            MoveHighlight::Wall(direction, position) => {
+                part_of_walls(square_type, row, col).contains(&(*direction, *position))
+            }
+            MoveHighlight::Pawn { .. } => false,
+        }
+    }
+}
+
+/// The player who reached their goal row, if any. Each pawn has to reach the row the other pawn started on.
+pub fn winner(board: &Board) -> Option<usize> {
+    let start = Board::new();
+    for pawn in 0..2 {
+        let goal_row = (0..DIMENSION)
+            .find(|row| (0..DIMENSION).any(|col| start.is_pawn(*row, col) == Some(1 - pawn)))?;
+        if (0..DIMENSION).any(|col| board.is_pawn(goal_row, col) == Some(pawn)) {
+            return Some(pawn);
+        }
+    }
+    None
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// A language of the UI. The messages of every locale are in a catalog in `locales/`, a message that is missing from
+/// a catalog is shown in English.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    Dutch,
+    German,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::English, Locale::Dutch, Locale::German];
+
+    /// The language subtag, like in `nl-BE`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Dutch => "nl",
+            Locale::German => "de",
+        }
+    }
+
+    /// The name of the language in the language itself, so it can be found in any locale.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::Dutch => "Nederlands",
+            Locale::German => "Deutsch",
+        }
+    }
+
+    /// The locale of a language tag like `nl-BE`, or the `nl_BE.UTF-8` of `LANG`.
+    pub fn from_language_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split(['-', '_', '.']).next()?.to_ascii_lowercase();
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code() == language)
+    }
+
+    /// The first of the preferred languages of the user that has a catalog, English if there is none.
+    pub fn negotiate<'a>(preferred: impl IntoIterator<Item = &'a str>) -> Locale {
+        preferred
+            .into_iter()
+            .find_map(Locale::from_language_tag)
+            .unwrap_or_default()
+    }
+
+    fn catalog_json(&self) -> &'static str {
+        match self {
+            Locale::English => include_str!("../locales/en.json"),
+            Locale::Dutch => include_str!("../locales/nl.json"),
+            Locale::German => include_str!("../locales/de.json"),
+        }
+    }
+
+    // Every catalog is parsed once, the first time a message of its locale is asked for.
+    fn catalog(&self) -> &'static HashMap<String, String> {
+        static CATALOGS: [OnceLock<HashMap<String, String>>; 3] =
+            [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+        CATALOGS[*self as usize].get_or_init(|| {
+            serde_json::from_str(self.catalog_json()).unwrap_or_else(|err| {
+                log::warn!("The {} catalog is broken: {}", self.code(), err);
+                HashMap::new()
+            })
+        })
+    }
+
+    /// The message with this key, in English if the catalog of the locale doesn't have it yet.
+    pub fn text(&self, key: &str) -> &'static str {
+        self.catalog()
+            .get(key)
+            .or_else(|| Locale::English.catalog().get(key))
+            .map(String::as_str)
+            .unwrap_or_else(|| {
+                log::warn!("No message {}", key);
+                ""
+            })
+    }
+
+    /// The message with this key, with every `{name}` in it replaced by its value.
+    pub fn format(&self, key: &str, values: &[(&str, &str)]) -> String {
+        let mut message = self.text(key).to_string();
+        for (name, value) in values {
+            message = message.replace(&format!("{{{}}}", name), value);
+        }
+        message
+    }
+
+    /// The name of player 0 or 1, like `WHITE`.
+    pub fn player(&self, player: usize) -> &'static str {
+        self.text(if player == 0 { "white" } else { "black" })
+    }
+
+    /// A whole number with the thousands grouped, `1,234,567` in English and `1.234.567` in Dutch.
+    pub fn count(&self, number: u64) -> String {
+        let digits = number.to_string();
+        let mut grouped = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(self.group_separator());
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// A fraction from 0 to 1 as a percentage with `decimals` decimals, like `62.5%`, `62,5%` or `62,5 %`.
+    pub fn percent(&self, fraction: f32, decimals: usize) -> String {
+        let number = format!("{:.*}", decimals, fraction * 100.0)
+            .replace('.', &self.decimal_separator().to_string());
+        match self {
+            // With a space that doesn't break, so the sign stays with the number.
+            Locale::German => format!("{}\u{a0}%", number),
+            Locale::English | Locale::Dutch => format!("{}%", number),
+        }
+    }
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            Locale::English => '.',
+            Locale::Dutch | Locale::German => ',',
+        }
+    }
+
+    fn group_separator(&self) -> char {
+        match self {
+            Locale::English => ',',
+            Locale::Dutch | Locale::German => '.',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_catalog_has_every_message() {
+        let english = Locale::English.catalog();
+        assert!(!english.is_empty());
+        for locale in Locale::ALL {
+            let mut keys: Vec<&String> = locale.catalog().keys().collect();
+            let mut english_keys: Vec<&String> = english.keys().collect();
+            keys.sort();
+            english_keys.sort();
+            assert_eq!(keys, english_keys, "the {} catalog", locale.code());
+        }
+    }
+
+    #[test]
+    fn every_tutorial_step_has_its_messages() {
+        for step in crate::tutorial::TUTORIAL {
+            for key in [step.title, step.instructions, step.hint, step.success] {
+                assert!(Locale::English.catalog().contains_key(key), "{}", key);
+            }
+        }
+    }
+
+    #[test]
+    fn numbers_follow_the_locale() {
+        assert_eq!(Locale::English.count(1234567), "1,234,567");
+        assert_eq!(Locale::Dutch.count(1234567), "1.234.567");
+        assert_eq!(Locale::English.percent(0.625, 1), "62.5%");
+        assert_eq!(Locale::German.percent(0.625, 1), "62,5\u{a0}%");
+    }
+}
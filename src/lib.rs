@@ -0,0 +1,15 @@
+pub mod analysis;
+pub mod book_manifest;
+pub mod correspondence;
+pub mod diagram;
+pub mod engine;
+pub mod explorer;
+pub mod game_result;
+pub mod grid;
+pub mod i18n;
+pub mod native;
+pub mod packed_book;
+pub mod puzzle;
+pub mod share;
+pub mod text_board;
+pub mod tutorial;
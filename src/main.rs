@@ -1,35 +1,160 @@
+// Without the desktop feature there is no UI natively, only the engine in the library.
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod analysis_report;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
 mod board_fr;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod board_svg;
+#[cfg(target_arch = "wasm32")]
 mod calc_worker;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod correspondence_page;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod diagram_export;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod opening_explorer;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod puzzle_trainer;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod quoridor_worker;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod rules_tutorial;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod settings;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod share_link;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod storage;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod theme;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+mod win_rate_graph;
 
-use std::collections::VecDeque;
-use std::ops::Deref;
-
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
 use dioxus::prelude::*;
-use gloo::timers::callback::Timeout;
-use gloo::timers::future::TimeoutFuture;
-use js_sys::Uint8Array;
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
-use web_sys::{
-    DedicatedWorkerGlobalScope, MessageEvent, Worker, WorkerGlobalScope, WorkerOptions, WorkerType,
-};
 
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
 use board_fr::QuoridorBoard;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use correspondence_page::CorrespondencePlay;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use opening_explorer::OpeningExplorer;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use puzzle_trainer::PuzzleTrainer;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use rules_tutorial::RulesTutorial;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use settings::{use_locale, Settings, SettingsPage};
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use quoridor_frontend::share::SharedLink;
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+use share_link::startup_link;
+#[cfg(target_arch = "wasm32")]
 pub use calc_worker::start_webworker;
-use calc_worker::*;
-use quoridor::{Move, PawnMove};
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
 
     dioxus_web::launch(app);
 }
 
+// Natively the engine runs on a thread next to the UI, run from the repository root so the book in `docs/precalc`
+// is found.
+#[cfg(all(not(target_arch = "wasm32"), feature = "desktop"))]
+fn main() {
+    dioxus_desktop::launch_cfg(
+        app,
+        dioxus_desktop::Config::new().with_custom_head(
+            r#"<link rel="stylesheet" href="docs/tailwind.css">"#.to_string(),
+        ),
+    );
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "desktop")))]
+fn main() {
+    eprintln!(
+        "quoridor_frontend runs in the browser, build it with `dx build`, or natively with `--features desktop`"
+    );
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+#[derive(Clone, Copy, PartialEq)]
+enum Page {
+    Game,
+    Explorer,
+    Puzzles,
+    Correspondence,
+    Rules,
+    Settings,
+}
+
+// The pages put the board and its side panel next to each other on wide screens. docs/tailwind.css only has the classes
+// that were used when it was generated and no breakpoints, so the part of the layout that depends on the width is here.
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
+const PAGE_LAYOUT: &str = "@media (min-width: 768px) { \
+    .page-columns { flex-direction: row; align-items: flex-start; } \
+    .side-panel { width: 20rem; } \
+    .game-panel { width: auto; } \
+    .board { padding: 1rem; } }";
+
+#[cfg(any(target_arch = "wasm32", feature = "desktop"))]
 fn app(cx: Scope) -> Element {
+    // A link to a game or position opens it on the game page, a link of a correspondence game on its own page.
+    let link = cx.use_hook(startup_link).clone();
+    let (token, link) = match link {
+        Some(SharedLink::Correspondence(token)) => (Some(token), None),
+        link => (None, link),
+    };
+    let page = use_state(cx, || {
+        if token.is_some() {
+            Page::Correspondence
+        } else {
+            Page::Game
+        }
+    });
+    // The game and the puzzles each run an engine. Once shown their pages stay mounted and are only hidden, so
+    // switching pages doesn't start a new engine and the game in progress is still there when coming back.
+    let puzzles_opened = use_state(cx, || false);
+    use_shared_state_provider(cx, Settings::load);
+    let theme = use_shared_state::<Settings>(cx)?.read().theme.theme();
+    let page_colors = format!("background-color: {}; color: {};", theme.page_background, theme.page_text);
+    let locale = use_locale(cx);
+    let tab_class = |tab: Page| {
+        if *page.get() == tab {
+            "bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        } else {
+            "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        }
+    };
+    let page_layout = PAGE_LAYOUT;
+    let shown = |shown_page: Page| if *page.get() == shown_page { "" } else { "display: none;" };
     render! {
         rsx! {
-            div {
-       QuoridorBoard  {}
+            div { style: "min-height: 100vh; {page_colors}",
+                style { "{page_layout}" },
+                div { class: "flex flex-wrap justify-center gap-2 p-2",
+                    button { class: tab_class(Page::Game), onclick: move |_| page.set(Page::Game), locale.text("tab_play") },
+                    button { class: tab_class(Page::Explorer), onclick: move |_| page.set(Page::Explorer), locale.text("tab_openings") },
+                    button { class: tab_class(Page::Puzzles), onclick: move |_| {
+                        puzzles_opened.set(true);
+                        page.set(Page::Puzzles);
+                    }, locale.text("tab_puzzles") },
+                    button { class: tab_class(Page::Correspondence), onclick: move |_| page.set(Page::Correspondence), locale.text("tab_correspondence") },
+                    button { class: tab_class(Page::Rules), onclick: move |_| page.set(Page::Rules), locale.text("tab_rules") },
+                    button { class: tab_class(Page::Settings), onclick: move |_| page.set(Page::Settings), locale.text("tab_settings") },
+                },
+                div { style: shown(Page::Game), QuoridorBoard { link: link.clone() } },
+                if *puzzles_opened.get() {
+                    rsx! { div { style: shown(Page::Puzzles), PuzzleTrainer {} } }
+                }
+                match page.get() {
+                    Page::Game | Page::Puzzles => rsx! { div {} },
+                    Page::Explorer => rsx! { OpeningExplorer {} },
+                    Page::Correspondence => rsx! { CorrespondencePlay { token: token.clone() } },
+                    Page::Rules => rsx! { RulesTutorial {} },
+                    Page::Settings => rsx! { SettingsPage {} },
+                }
             }}
     }
 }
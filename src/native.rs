@@ -0,0 +1,235 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use quoridor::{Board, MonteCarloTree, PreCalc};
+
+use crate::book_manifest::{verified_tree, BookManifest, MANIFEST_FILE};
+use crate::engine::*;
+use crate::packed_book::{pack, PackedBook, PACKED_BOOK_FILE};
+
+/// Reads the manifest of the book in `dir`.
+pub fn read_manifest(dir: &Path) -> Result<BookManifest, BookError> {
+    Ok(serde_json::from_slice(&std::fs::read(
+        dir.join(MANIFEST_FILE),
+    )?)?)
+}
+
+/// What `pack_directory` wrote.
+pub struct PackReport {
+    pub trees: usize,
+    pub unpacked_size: usize,
+    pub packed_size: usize,
+    /// The positions of the book that have no tree matching the manifest, with the reason.
+    pub skipped: Vec<String>,
+}
+
+fn book_encodings(dir: &Path) -> Result<Vec<String>, BookError> {
+    let json: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("to_precalc.json"))?)?;
+    Ok(json["scores_player_zero"]
+        .as_object()
+        .ok_or("to_precalc.json has no scores_player_zero")?
+        .keys()
+        .cloned()
+        .collect())
+}
+
+/// Packs the trees of the book in `dir` into `PACKED_BOOK_FILE`. Only the positions listed in `to_precalc.json` whose
+/// tree matches the manifest are packed, and the pack is read back before it is written, so a broken book never gets
+/// deployed.
+pub fn pack_directory(dir: &Path) -> Result<PackReport, BookError> {
+    let manifest = read_manifest(dir)?;
+    let mut trees = vec![];
+    let mut unpacked_size = 0;
+    let mut skipped = vec![];
+    for encoding in book_encodings(dir)? {
+        let path = dir.join("precalc").join(format!("{}.mc_node", encoding));
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            // The position still has its score, the engine just searches it itself.
+            Err(err) => {
+                skipped.push(format!("{}: {}", path.display(), err));
+                continue;
+            }
+        };
+        if let Err(err) = manifest.verify(&encoding, &bytes) {
+            skipped.push(err.to_string());
+            continue;
+        }
+        unpacked_size += bytes.len();
+        trees.push((encoding, bytes));
+    }
+    let encodings: Vec<String> = trees.iter().map(|(encoding, _)| encoding.clone()).collect();
+    let packed = pack(trees)?;
+
+    let book = PackedBook::from_bytes(packed.clone())?;
+    for encoding in &encodings {
+        manifest.verify(encoding, &book.tree_bytes(encoding)?)?;
+    }
+    std::fs::write(dir.join(PACKED_BOOK_FILE), &packed)?;
+    Ok(PackReport {
+        trees: book.index().len(),
+        unpacked_size,
+        packed_size: packed.len(),
+        skipped,
+    })
+}
+
+/// Reads the book from a local copy of the `precalc` folder the site serves, like `docs/precalc`. The trees come from
+/// the packed book if there is one, otherwise from the separate `.mc_node` files, and are checked with the manifest.
+pub struct DirectoryBook {
+    pub dir: PathBuf,
+    packed: Option<PackedBook>,
+    manifest: Option<BookManifest>,
+}
+
+impl DirectoryBook {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let packed = match std::fs::read(dir.join(PACKED_BOOK_FILE)) {
+            Ok(bytes) => match PackedBook::from_bytes(bytes) {
+                Ok(packed) => Some(packed),
+                Err(err) => {
+                    log::warn!("Ignoring {}: {}", PACKED_BOOK_FILE, err);
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        let manifest = match read_manifest(&dir) {
+            Ok(manifest) => Some(manifest),
+            Err(err) => {
+                log::warn!(
+                    "Not using the book trees, {} can't be read: {}",
+                    MANIFEST_FILE,
+                    err
+                );
+                None
+            }
+        };
+        DirectoryBook {
+            dir,
+            packed,
+            manifest,
+        }
+    }
+}
+
+impl DirectoryBook {
+    fn read_scores(&self) -> Result<PreCalc, BookError> {
+        let json = std::fs::read(self.dir.join("to_precalc.json"))?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    fn read_tree(&self, board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+        if self
+            .manifest
+            .as_ref()
+            .is_some_and(|manifest| !manifest.has_tree(&board.encode()))
+        {
+            return Ok(None);
+        }
+        let bytes = match &self.packed {
+            Some(packed) => packed.tree_bytes(&board.encode())?,
+            None => std::fs::read(
+                self.dir
+                    .join("precalc")
+                    .join(format!("{}.mc_node", board.encode())),
+            )?,
+        };
+        verified_tree(self.manifest.as_ref(), board, &bytes).map(Some)
+    }
+}
+
+impl BookFetcher for DirectoryBook {
+    async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError> {
+        Ok(Rc::new(self.read_scores()?))
+    }
+
+    async fn tree(&mut self, board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+        self.read_tree(board)
+    }
+}
+
+/// One `DirectoryBook` for many sessions on the same thread, like the games of a match. The packed book and the
+/// manifest are read and the scores parsed once, instead of for every session.
+#[derive(Clone)]
+pub struct SharedBook {
+    book: Rc<DirectoryBook>,
+    scores: Rc<PreCalc>,
+}
+
+impl SharedBook {
+    /// Without scores the sessions play without the book, like a `DirectoryBook` whose scores can't be read.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let book = DirectoryBook::new(dir);
+        let scores = book.read_scores().unwrap_or_else(|err| {
+            log::warn!("{}", err);
+            PreCalc::new()
+        });
+        SharedBook {
+            book: Rc::new(book),
+            scores: Rc::new(scores),
+        }
+    }
+}
+
+impl BookFetcher for SharedBook {
+    async fn board_scores(&mut self) -> Result<Rc<PreCalc>, BookError> {
+        Ok(self.scores.clone())
+    }
+
+    async fn tree(&mut self, board: &Board) -> Result<Option<MonteCarloTree>, BookError> {
+        self.book.read_tree(board)
+    }
+}
+
+/// Commands coming from another thread. The session ends once the sender is dropped.
+pub struct ChannelCommands(pub Receiver<UserCommand>);
+
+impl CommandSource for ChannelCommands {
+    async fn recv(&mut self) -> Option<UserCommand> {
+        self.0.recv().ok()
+    }
+
+    async fn recv_or_yield(&mut self) -> Option<UserCommand> {
+        self.0.try_recv().ok()
+    }
+}
+
+/// Sends the updates of the session to another thread.
+pub struct ChannelUpdates(pub Sender<CalculateUpdate>);
+
+impl UpdateSink for ChannelUpdates {
+    fn send_update(&self, update: CalculateUpdate) {
+        // The other side is gone if this fails, and then nobody is interested in the update anymore.
+        let _ = self.0.send(update);
+    }
+}
+
+/// Runs a session with the book from `book` on its own thread, so it can keep searching while the caller waits for
+/// input. The session stops when the returned sender is dropped.
+pub fn spawn_session(book: DirectoryBook) -> (Sender<UserCommand>, Receiver<CalculateUpdate>) {
+    let (update_sender, update_receiver) = channel();
+    let command_sender = spawn_session_with_updates(book, ChannelUpdates(update_sender));
+    (command_sender, update_receiver)
+}
+
+/// Like `spawn_session`, with the updates going to `updates`.
+pub fn spawn_session_with_updates(
+    book: DirectoryBook,
+    updates: impl UpdateSink + Send + 'static,
+) -> Sender<UserCommand> {
+    let (command_sender, command_receiver) = channel();
+    thread::spawn(move || {
+        futures::executor::block_on(async move {
+            EngineSession::new(ChannelCommands(command_receiver), updates, book)
+                .await
+                .run()
+                .await;
+        })
+    });
+    command_sender
+}
@@ -0,0 +1,166 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use futures::lock::Mutex;
+
+use quoridor::*;
+
+use quoridor_frontend::engine::{book_position, BookFetcher};
+use quoridor_frontend::explorer::*;
+
+use crate::board_fr::{BoardGrid, OrientationToggle};
+use crate::settings::use_locale;
+
+#[cfg(target_arch = "wasm32")]
+fn explorer_book() -> impl BookFetcher {
+    crate::calc_worker::HttpBook::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn explorer_book() -> impl BookFetcher {
+    quoridor_frontend::native::DirectoryBook::new("docs/precalc")
+}
+
+// Walks through the precalculated book, starting from the initial position. The user can click a continuation or make
+// any move on the board, the list then shows what the book knows about the new position.
+pub fn OpeningExplorer(cx: Scope) -> Element {
+    let locale = use_locale(cx);
+    let board_flipped = use_state(cx, || false);
+    let path: &UseRef<Vec<Move>> = use_ref(cx, Vec::new);
+
+    // One book for the whole page, so the packed book index is only loaded once.
+    let book = cx.use_hook(|| Rc::new(Mutex::new(explorer_book())));
+
+    let pre_calc = use_future(cx, (), |_| {
+        let book = book.clone();
+        async move {
+            match book.lock().await.board_scores().await {
+                Ok(pre_calc) => Some(pre_calc),
+                Err(err) => {
+                    log::warn!("{}", err);
+                    None
+                }
+            }
+        }
+    });
+    let pre_calc = pre_calc.value().cloned().flatten();
+
+    let mut board = Board::new();
+    let mut path_notation = vec![];
+    for game_move in path.read().iter() {
+        path_notation.push(game_move.to_quoridor_strat_notation(&board));
+        board.game_move(*game_move);
+    }
+    let encoding = board.encode();
+
+    let continuations = use_future(cx, (&encoding, &pre_calc.is_some()), |(encoding, _)| {
+        let board = board.clone();
+        let pre_calc = pre_calc.clone();
+        let book = book.clone();
+        async move {
+            let Some(pre_calc) = pre_calc else {
+                return (encoding, vec![]);
+            };
+            let tree = match book_position(&pre_calc, &board) {
+                Some((book_board, _, mirrored)) => match book.lock().await.tree(&book_board).await {
+                    Ok(tree) => tree.map(|tree| (tree, mirrored)),
+                    Err(err) => {
+                        log::warn!("{}", err);
+                        None
+                    }
+                },
+                None => None,
+            };
+            (encoding, book_continuations(&board, &pre_calc, tree))
+        }
+    });
+    // Until the new position is loaded the old continuations are still there, they should not be clickable.
+    let continuations: &[BookContinuation] = match continuations.value() {
+        Some((loaded, continuations)) if *loaded == encoding => continuations,
+        _ => &[],
+    };
+
+    let in_book = pre_calc
+        .as_ref()
+        .and_then(|pre_calc| book_position(pre_calc, &board));
+    let path_text = path_notation.join(" ");
+
+    cx.render(rsx! {
+        div { class: "page-columns flex flex-col justify-center items-center gap-4",
+        div { class: "flex flex-col items-center",
+            BoardGrid {
+                board: board.clone(),
+                flipped: *board_flipped.get(),
+                interactive: true,
+                on_move: move |game_move: Move| {
+                    path.with_mut(|path| path.push(game_move));
+                },
+            },
+            div { class: "w-full p-4 flex flex-col items-center",
+                div { class: "text-2xl font-semibold", locale.text("explorer_line") },
+                div { class: "text-xl", "{path_text}" }
+            }
+        },
+        div { class: "side-panel flex flex-col space-y-2 w-full p-2",
+            div { class: "flex space-x-2",
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| {
+                        path.with_mut(|path| path.clear());
+                    },
+                    locale.text("explorer_start")
+                },
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| {
+                        path.with_mut(|path| path.pop());
+                    },
+                    locale.text("back")
+                },
+                OrientationToggle { flipped: board_flipped },
+            },
+            match (&pre_calc, &in_book) {
+                (None, _) => rsx! { div { class: "text-xl", locale.text("explorer_loading") } },
+                (Some(_), None) => rsx! { div { class: "text-xl", locale.text("explorer_not_in_book") } },
+                (Some(_), Some((_, win_rate_zero, mirrored))) => {
+                    let win_rates = locale.format(
+                        "explorer_win_rates",
+                        &[("white", &locale.percent(*win_rate_zero, 1)), ("black", &locale.percent(1.0 - *win_rate_zero, 1))],
+                    );
+                    rsx! {
+                        div { class: "text-xl font-semibold", "{win_rates}" },
+                        if *mirrored {
+                            rsx! { div { class: "text-sm text-gray-600", locale.text("explorer_stored_mirrored") } }
+                        }
+                    }
+                },
+            },
+            continuations.iter().map(|continuation| {
+                let game_move = continuation.game_move;
+                let visits = match continuation.visits {
+                    Some(visits) => locale.format("explorer_visits", &[("visits", &locale.count(visits.into()))]),
+                    None => "-".to_string(),
+                };
+                let white = locale.format("explorer_white_short", &[("win_rate", &locale.percent(continuation.win_rate_zero, 1))]);
+                let black = locale.format("explorer_black_short", &[("win_rate", &locale.percent(1.0 - continuation.win_rate_zero, 1))]);
+                rsx! {
+                    button {
+                        key: "{continuation.notation}",
+                        class: "flex justify-between items-center bg-gray-100 hover:bg-amber-200 py-1 px-2 rounded",
+                        onclick: move |_| {
+                            path.with_mut(|path| path.push(game_move));
+                        },
+                        span { class: "font-bold w-12 text-left", "{continuation.notation}" },
+                        span { "{white}" },
+                        span { "{black}" },
+                        span { class: "text-gray-600", "{visits}" },
+                        if continuation.mirrored {
+                            rsx! { span { class: "text-xs bg-amber-500 text-white rounded px-1", locale.text("explorer_mirror") } }
+                        }
+                    }
+                }
+            })
+        }
+        }
+    })
+}
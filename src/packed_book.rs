@@ -0,0 +1,258 @@
+// The whole opening book in one file, so the browser doesn't have to fetch every tree separately.
+//
+// The file starts with a header of `HEADER_LEN` bytes: the magic `QBOK`, the format version and the number of trees,
+// all little endian `u32`s. Then follows the index, one `INDEX_ENTRY_LEN` entry per tree with the hash of the
+// encoding of its position, the offset of its entry from the start of the file and the length of the entry, sorted by
+// hash. An entry is the length of the encoding as a little endian `u16`, the encoding itself, so a lookup can tell a
+// hash collision from the position it asked for, and the `.mc_node` bytes of the tree, deflate compressed. Reading the
+// header and the index first, a single entry can be fetched with an HTTP range request.
+use std::ops::Range;
+
+use crate::engine::BookError;
+
+pub const MAGIC: &[u8; 4] = b"QBOK";
+pub const FORMAT_VERSION: u32 = 2;
+pub const HEADER_LEN: usize = 12;
+pub const INDEX_ENTRY_LEN: usize = 20;
+/// The name of the packed book next to `to_precalc.json`.
+pub const PACKED_BOOK_FILE: &str = "book.qbook";
+
+/// FNV-1a, the book positions are looked up by this hash of their encoding.
+pub fn encoding_hash(encoding: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in encoding.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+#[derive(Clone, Copy, Debug)]
+struct IndexEntry {
+    hash: u64,
+    offset: u64,
+    length: u32,
+}
+
+/// Where the trees are in a packed book.
+#[derive(Clone, Debug)]
+pub struct PackedIndex {
+    entries: Vec<IndexEntry>,
+}
+
+impl PackedIndex {
+    /// How many bytes from the start of the file `parse` needs, given at least the first `HEADER_LEN` bytes.
+    pub fn len_from_header(header: &[u8]) -> Result<usize, BookError> {
+        if header.len() < HEADER_LEN || &header[..4] != MAGIC {
+            return Err("not a packed book")?;
+        }
+        let version = read_u32(header, 4);
+        if version != FORMAT_VERSION {
+            return Err(format!("packed book version {} is not supported", version))?;
+        }
+        usize::try_from(read_u32(header, 8))
+            .ok()
+            .and_then(|trees| trees.checked_mul(INDEX_ENTRY_LEN))
+            .and_then(|index| index.checked_add(HEADER_LEN))
+            .ok_or_else(|| "packed book index is too large".into())
+    }
+
+    /// Reads the index from the start of a packed book, the trees don't have to be there.
+    pub fn parse(bytes: &[u8]) -> Result<PackedIndex, BookError> {
+        let len = Self::len_from_header(bytes)?;
+        if bytes.len() < len {
+            return Err("packed book index is truncated")?;
+        }
+        let entries = bytes[HEADER_LEN..len]
+            .chunks_exact(INDEX_ENTRY_LEN)
+            .map(|entry| IndexEntry {
+                hash: read_u64(entry, 0),
+                offset: read_u64(entry, 8),
+                length: read_u32(entry, 16),
+            })
+            .collect();
+        Ok(PackedIndex { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The bytes of the entry with this hash of `encoding` in the file, pass them to `unpack` to check that they are
+    /// of `encoding`. The encoding has to be in the orientation the book stores it in, see `book_position`.
+    pub fn find(&self, encoding: &str) -> Option<Range<usize>> {
+        let hash = encoding_hash(encoding);
+        let position = self
+            .entries
+            .binary_search_by_key(&hash, |entry| entry.hash)
+            .ok()?;
+        let entry = self.entries[position];
+        let start = usize::try_from(entry.offset).ok()?;
+        let end = start.checked_add(usize::try_from(entry.length).ok()?)?;
+        Some(start..end)
+    }
+}
+
+/// Turns an entry of the packed book back into the `.mc_node` bytes of its tree, if it is the entry of `encoding`.
+pub fn unpack(encoding: &str, entry: &[u8]) -> Result<Vec<u8>, BookError> {
+    let key_len = entry
+        .get(..2)
+        .map(|len| u16::from_le_bytes([len[0], len[1]]) as usize)
+        .ok_or("packed book entry is truncated")?;
+    let key = entry
+        .get(2..2 + key_len)
+        .ok_or("packed book entry is truncated")?;
+    if key != encoding.as_bytes() {
+        return Err(format!(
+            "the packed book has {} where {} was looked up",
+            String::from_utf8_lossy(key),
+            encoding
+        )
+        .into());
+    }
+    miniz_oxide::inflate::decompress_to_vec(&entry[2 + key_len..])
+        .map_err(|err| format!("could not decompress the tree: {}", err).into())
+}
+
+/// Packs the `.mc_node` bytes of every position, given by its encoding, into one file.
+pub fn pack(trees: Vec<(String, Vec<u8>)>) -> Result<Vec<u8>, BookError> {
+    let mut trees: Vec<(u64, String, Vec<u8>)> = trees
+        .into_iter()
+        .map(|(encoding, bytes)| (encoding_hash(&encoding), encoding, bytes))
+        .collect();
+    trees.sort_by_key(|(hash, _, _)| *hash);
+    for pair in trees.windows(2) {
+        if pair[0].0 == pair[1].0 {
+            return Err(format!("{} and {} have the same hash", pair[0].1, pair[1].1).into());
+        }
+    }
+
+    let index_len = HEADER_LEN + trees.len() * INDEX_ENTRY_LEN;
+    let mut file = Vec::with_capacity(index_len);
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    let count = u32::try_from(trees.len()).map_err(|_| "too many trees for a packed book")?;
+    file.extend_from_slice(&count.to_le_bytes());
+    let mut entries = vec![];
+    for (hash, encoding, bytes) in &trees {
+        let key_len =
+            u16::try_from(encoding.len()).map_err(|_| format!("{} is too long", encoding))?;
+        let mut entry = key_len.to_le_bytes().to_vec();
+        entry.extend_from_slice(encoding.as_bytes());
+        entry.extend_from_slice(&miniz_oxide::deflate::compress_to_vec(bytes, 9));
+        let length = u32::try_from(entry.len())
+            .map_err(|_| format!("the tree of {} is too large", encoding))?;
+        file.extend_from_slice(&hash.to_le_bytes());
+        file.extend_from_slice(&((index_len + entries.len()) as u64).to_le_bytes());
+        file.extend_from_slice(&length.to_le_bytes());
+        entries.extend_from_slice(&entry);
+    }
+    file.extend_from_slice(&entries);
+    Ok(file)
+}
+
+/// A packed book that is loaded as a whole.
+pub struct PackedBook {
+    bytes: Vec<u8>,
+    index: PackedIndex,
+}
+
+impl PackedBook {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<PackedBook, BookError> {
+        let index = PackedIndex::parse(&bytes)?;
+        Ok(PackedBook { bytes, index })
+    }
+
+    pub fn index(&self) -> &PackedIndex {
+        &self.index
+    }
+
+    /// The `.mc_node` bytes of the tree of the position with `encoding`, check them with the manifest before use.
+    pub fn tree_bytes(&self, encoding: &str) -> Result<Vec<u8>, BookError> {
+        let range = self.index.find(encoding).ok_or("not in the packed book")?;
+        let entry = self.bytes.get(range).ok_or("packed book is truncated")?;
+        unpack(encoding, entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trees() -> Vec<(String, Vec<u8>)> {
+        vec![
+            ("0;10E1;10E9".to_string(), vec![1, 2, 3, 4]),
+            ("1;10E2;10E9".to_string(), vec![5; 1000]),
+            ("2;10E2;10E8".to_string(), vec![]),
+        ]
+    }
+
+    #[test]
+    fn every_tree_reads_back_from_the_packed_book() {
+        let book = PackedBook::from_bytes(pack(trees()).unwrap()).unwrap();
+        assert_eq!(book.index().len(), 3);
+        for (encoding, bytes) in trees() {
+            assert_eq!(book.tree_bytes(&encoding).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn the_index_alone_finds_the_entries() {
+        let packed = pack(trees()).unwrap();
+        let len = PackedIndex::len_from_header(&packed[..HEADER_LEN]).unwrap();
+        let index = PackedIndex::parse(&packed[..len]).unwrap();
+        let range = index.find("1;10E2;10E9").unwrap();
+        assert!(range.start >= len && range.end <= packed.len());
+        assert_eq!(
+            unpack("1;10E2;10E9", &packed[range]).unwrap(),
+            vec![5; 1000]
+        );
+    }
+
+    #[test]
+    fn a_position_that_is_not_packed_is_not_found() {
+        let book = PackedBook::from_bytes(pack(trees()).unwrap()).unwrap();
+        assert!(book.index().find("3;10E3;10E8").is_none());
+        assert!(book.tree_bytes("3;10E3;10E8").is_err());
+    }
+
+    #[test]
+    fn an_entry_of_another_position_is_refused() {
+        let packed = pack(trees()).unwrap();
+        let index = PackedIndex::parse(&packed).unwrap();
+        let range = index.find("0;10E1;10E9").unwrap();
+        assert!(unpack("1;10E2;10E9", &packed[range]).is_err());
+    }
+
+    #[test]
+    fn a_truncated_book_is_refused() {
+        let packed = pack(trees()).unwrap();
+        assert!(PackedIndex::parse(&packed[..HEADER_LEN + 5]).is_err());
+        // The last entry in the file is cut off.
+        let book = PackedBook::from_bytes(packed[..packed.len() - 1].to_vec()).unwrap();
+        assert!(trees()
+            .iter()
+            .any(|(encoding, _)| book.tree_bytes(encoding).is_err()));
+    }
+
+    #[test]
+    fn another_format_is_refused() {
+        let mut packed = pack(trees()).unwrap();
+        packed[4] = 1;
+        assert!(PackedBook::from_bytes(packed.clone()).is_err());
+        packed[0] = b'X';
+        assert!(PackedBook::from_bytes(packed).is_err());
+    }
+}
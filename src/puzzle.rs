@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use quoridor::Board;
+
+use crate::engine::BookError;
+use crate::text_board::parse_move;
+
+/// Where `quoridor-puzzles` writes the puzzles, the app is built with this file.
+pub const PUZZLES_FILE: &str = "docs/puzzles.json";
+
+/// A position where the player to move can win, but only with the right moves.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    /// The `Board::encode()` of the position, also how the results of the puzzle are kept apart.
+    pub encoding: String,
+    /// The player who has to find the moves, 0 for white.
+    pub to_move: usize,
+    /// The winning lines in Quoridor Strats notation. Every line starts with a move of `to_move` and alternates with
+    /// the defense.
+    pub solutions: Vec<Vec<String>>,
+    /// The chance `to_move` wins, from the search that found the puzzle.
+    pub win_rate: f32,
+}
+
+/// How an attempt at a puzzle is going, after the moves played since the puzzle position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PuzzleOutcome {
+    Playing,
+    Solved,
+    /// The last move of the solver is in none of the lines, these were the moves that would have been right.
+    Wrong {
+        expected: Vec<String>,
+    },
+}
+
+impl Puzzle {
+    pub fn board(&self) -> Board {
+        Board::decode(&self.encoding).unwrap()
+    }
+
+    // Every line has to be playable from the position, otherwise the puzzle could never be solved.
+    fn check(&self) -> Result<(), BookError> {
+        let board = Board::decode(&self.encoding)
+            .map_err(|err| format!("puzzle {} can't be decoded: {:?}", self.encoding, err))?;
+        if board.turn % 2 != self.to_move {
+            return Err(format!(
+                "puzzle {} is for player {}, but the other player is to move",
+                self.encoding, self.to_move
+            ))?;
+        }
+        if self.solutions.is_empty() {
+            return Err(format!("puzzle {} has no solution", self.encoding))?;
+        }
+        for line in &self.solutions {
+            let mut board = board.clone();
+            for notation in line {
+                let game_move = parse_move(&board, notation).ok_or_else(|| {
+                    format!("puzzle {} has the illegal move {}", self.encoding, notation)
+                })?;
+                board.game_move(game_move);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the moves played so far against the solutions. Once the defense plays a move none of the lines
+    /// expected, all moves of the solver were right up to there and the puzzle counts as solved.
+    pub fn outcome(&self, played: &[String]) -> PuzzleOutcome {
+        let mut lines: Vec<&Vec<String>> = self.solutions.iter().collect();
+        for (index, notation) in played.iter().enumerate() {
+            let matching: Vec<&Vec<String>> = lines
+                .iter()
+                .filter(|line| {
+                    line.get(index)
+                        .is_some_and(|expected| expected.eq_ignore_ascii_case(notation))
+                })
+                .copied()
+                .collect();
+            if matching.is_empty() {
+                if index % 2 == 1 {
+                    return PuzzleOutcome::Solved;
+                }
+                let mut expected: Vec<String> = lines
+                    .iter()
+                    .filter_map(|line| line.get(index).cloned())
+                    .collect();
+                expected.sort();
+                expected.dedup();
+                return PuzzleOutcome::Wrong { expected };
+            }
+            if matching.iter().any(|line| line.len() == index + 1) {
+                return PuzzleOutcome::Solved;
+            }
+            lines = matching;
+        }
+        PuzzleOutcome::Playing
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct PuzzleSet {
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl PuzzleSet {
+    /// Reads a puzzle set and checks every puzzle in it, so a broken puzzle is never shown.
+    pub fn parse(json: &str) -> Result<PuzzleSet, BookError> {
+        let puzzle_set: PuzzleSet = serde_json::from_str(json)?;
+        for puzzle in &puzzle_set.puzzles {
+            puzzle.check()?;
+        }
+        Ok(puzzle_set)
+    }
+
+    /// Whether the set has `board` or its mirror image already.
+    pub fn contains(&self, board: &Board) -> bool {
+        let (encoding, mirror) = (board.encode(), board.encode_mirror());
+        self.puzzles
+            .iter()
+            .any(|puzzle| puzzle.encoding == encoding || puzzle.encoding == mirror)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct PuzzleScore {
+    pub solved: u32,
+    pub failed: u32,
+}
+
+/// How the user did on every puzzle they tried, by the encoding of the puzzle.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct PuzzleResults {
+    pub scores: BTreeMap<String, PuzzleScore>,
+}
+
+impl PuzzleResults {
+    pub fn record(&mut self, encoding: &str, solved: bool) {
+        let score = self.scores.entry(encoding.to_string()).or_default();
+        if solved {
+            score.solved += 1;
+        } else {
+            score.failed += 1;
+        }
+    }
+
+    pub fn score(&self, encoding: &str) -> PuzzleScore {
+        self.scores.get(encoding).copied().unwrap_or_default()
+    }
+
+    /// The first puzzle that was never solved, so the user continues where they left off.
+    pub fn next_unsolved(&self, puzzles: &[Puzzle]) -> usize {
+        puzzles
+            .iter()
+            .position(|puzzle| self.score(&puzzle.encoding).solved == 0)
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,165 @@
+use dioxus::prelude::*;
+
+use quoridor::*;
+
+use quoridor_frontend::puzzle::{Puzzle, PuzzleOutcome, PuzzleResults, PuzzleSet};
+
+use crate::board_fr::{white_at_bottom, BoardGrid, OrientationToggle};
+use crate::quoridor_worker::*;
+use crate::settings::use_locale;
+use crate::storage;
+
+const PUZZLE_RESULTS_KEY: &str = "puzzle_results";
+
+// The puzzles are built into the app, `quoridor-puzzles` adds new ones to the file.
+fn puzzle_set() -> PuzzleSet {
+    PuzzleSet::parse(include_str!("../docs/puzzles.json")).unwrap_or_else(|err| {
+        log::warn!("Could not load the puzzles: {}", err);
+        PuzzleSet::default()
+    })
+}
+
+// Shows a position from `docs/puzzles.json`, the user has to find the winning moves and the engine plays the defense.
+// How often every puzzle was solved and failed is kept between visits.
+pub fn PuzzleTrainer(cx: Scope) -> Element {
+    let locale = use_locale(cx);
+    let puzzles: &Vec<Puzzle> = cx.use_hook(|| puzzle_set().puzzles);
+    let results: &UseRef<PuzzleResults> =
+        use_ref(cx, || storage::load(PUZZLE_RESULTS_KEY).unwrap_or_default());
+    let index = use_state(cx, || results.read().next_unsolved(puzzles));
+    let board_flipped = use_state(cx, || false);
+
+    let WorkerState {
+        worker,
+        board,
+        ai_player,
+        ..
+    } = use_webworker(cx);
+
+    // The board is reset in the same update that changes `index`, so the moves on the board are never judged
+    // against another puzzle than the one they were played on.
+    let start_puzzle = move |new_index: usize| {
+        index.set(new_index);
+        let Some(puzzle) = puzzles.get(new_index) else {
+            return;
+        };
+        board.set(BoardWithHistory::from_position(puzzle.board()));
+        worker.send_command(UserCommand::DecodeBoard(puzzle.encoding.clone()));
+        // The player who has to find the moves plays up the board.
+        board_flipped.set(white_at_bottom(false) != (puzzle.to_move == 0));
+        let defender = 1 - puzzle.to_move;
+        ai_player.set(Some(defender));
+        worker.send_command(UserCommand::SetAIPlayer(defender));
+    };
+    use_effect(cx, (), move |_| {
+        start_puzzle(*index.get());
+        async {}
+    });
+
+    let puzzle = puzzles.get(*index.get()).cloned();
+
+    let outcome = puzzle
+        .as_ref()
+        .map_or(PuzzleOutcome::Playing, |puzzle| puzzle.outcome(&board.read().historic_moves));
+    // Recorded once per attempt, when it's decided.
+    use_effect(cx, (&outcome,), |(outcome,)| {
+        if let Some(puzzle) = &puzzle {
+            let solved = match outcome {
+                PuzzleOutcome::Playing => None,
+                PuzzleOutcome::Solved => Some(true),
+                PuzzleOutcome::Wrong { .. } => Some(false),
+            };
+            if let Some(solved) = solved {
+                results.with_mut(|results| {
+                    results.record(&puzzle.encoding, solved);
+                    storage::save(PUZZLE_RESULTS_KEY, results);
+                });
+            }
+        }
+        async {}
+    });
+
+    let Some(puzzle) = puzzle else {
+        return cx.render(rsx! {
+            div { class: "text-xl p-4 text-center", locale.text("puzzle_none") }
+        });
+    };
+
+    let players_turn = board.read().board.turn % 2 == puzzle.to_move;
+    let score = results.read().score(&puzzle.encoding);
+    let solved_count = results
+        .read()
+        .scores
+        .values()
+        .filter(|score| score.solved > 0)
+        .count();
+    let count = puzzles.len();
+    let number_text = locale.format(
+        "puzzle_number",
+        &[("number", &locale.count(*index.get() as u64 + 1)), ("count", &locale.count(count as u64))],
+    );
+    let to_move = locale.format(
+        "puzzle_to_move",
+        &[("player", locale.player(puzzle.to_move)), ("win_rate", &locale.percent(puzzle.win_rate, 0))],
+    );
+    let score_text = locale.format(
+        "puzzle_score",
+        &[
+            ("solved", &locale.count(score.solved.into())),
+            ("failed", &locale.count(score.failed.into())),
+            ("solved_puzzles", &locale.count(solved_count as u64)),
+            ("count", &locale.count(count as u64)),
+        ],
+    );
+
+    cx.render(rsx! {
+        div { class: "page-columns flex flex-col justify-center items-center gap-4",
+        div { class: "flex flex-col items-center",
+            BoardGrid {
+                board: board.read().board.clone(),
+                highlights: board.read().last_moves(board.read().moves.len()),
+                flipped: *board_flipped.get(),
+                interactive: players_turn && outcome == PuzzleOutcome::Playing,
+                on_move: move |game_move: Move| {
+                    board.with_mut(|board| board.game_move(game_move));
+                    worker.send_command(UserCommand::GameMove(game_move));
+                },
+            },
+            div { class: "w-full p-4 flex flex-col items-center",
+                div { class: "text-2xl font-semibold", locale.text("puzzle_moves") },
+                div { class: "text-xl", "{board.read().historic_moves()}" }
+            }
+        },
+        div { class: "side-panel flex flex-col space-y-2 w-full p-2",
+            div { class: "text-2xl font-semibold", "{number_text}" },
+            div { class: "text-xl", "{to_move}" },
+            div { class: "text-sm text-gray-600", "{score_text}" },
+            match &outcome {
+                PuzzleOutcome::Playing if players_turn => rsx! { div { class: "text-xl", locale.text("puzzle_find_move") } },
+                PuzzleOutcome::Playing => rsx! { div { class: "text-xl", locale.text("puzzle_defending") } },
+                PuzzleOutcome::Solved => rsx! { div { class: "bg-green-500 text-white font-bold py-2 px-4 rounded", locale.text("puzzle_solved") } },
+                PuzzleOutcome::Wrong { expected } => {
+                    let wrong = locale.format(
+                        "puzzle_wrong",
+                        &[("expected", &expected.join(&format!(" {} ", locale.text("or"))))],
+                    );
+                    rsx! { div { class: "bg-red-500 text-white font-bold py-2 px-4 rounded", "{wrong}" } }
+                }
+            },
+            div { class: "flex space-x-2",
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| start_puzzle(*index.get()),
+                    locale.text("retry")
+                },
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| start_puzzle((*index.get() + 1) % count),
+                    locale.text("next")
+                },
+                OrientationToggle { flipped: board_flipped },
+            }
+        }
+        }
+    })
+}
@@ -0,0 +1,189 @@
+use dioxus::prelude::*;
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use futures::StreamExt;
+use log::info;
+
+pub use quoridor_frontend::analysis::{GameAnalysis, SavedGame, ANALYSIS_VISITS};
+pub use quoridor_frontend::engine::{
+    BoardWithHistory, BookStatus, CalculateUpdate, SearchLimit, UserCommand, Variety,
+};
+
+use crate::storage;
+
+const SAVED_GAME_KEY: &str = "saved_game";
+
+/// Where the engine runs. In the browser that is a web worker, natively it is a thread.
+pub trait WorkerBackend {
+    fn send_command(&self, command: UserCommand);
+}
+
+#[derive(Clone, Copy)]
+pub struct QuoridorWorker<'a> {
+    backend: &'a dyn WorkerBackend,
+}
+
+impl<'a> QuoridorWorker<'a> {
+    pub fn send_command(&self, command: UserCommand) {
+        log::info!("Sending command to worker: {:?}", command);
+        self.backend.send_command(command);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn new_backend(updates: UnboundedSender<CalculateUpdate>) -> Box<dyn WorkerBackend> {
+    Box::new(crate::calc_worker::new_web_worker(updates))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WorkerBackend for std::sync::mpsc::Sender<UserCommand> {
+    fn send_command(&self, command: UserCommand) {
+        // Only fails when the engine thread is gone, and then there is nobody left to tell.
+        let _ = self.send(command);
+    }
+}
+
+// Natively the book is read from the folder the site is built from.
+#[cfg(not(target_arch = "wasm32"))]
+fn new_backend(updates: UnboundedSender<CalculateUpdate>) -> Box<dyn WorkerBackend> {
+    use quoridor_frontend::native::{spawn_session_with_updates, DirectoryBook};
+
+    Box::new(spawn_session_with_updates(
+        DirectoryBook::new("docs/precalc"),
+        updates,
+    ))
+}
+
+// The analysis searched every position equally long, so its win rates replace the ones from the game.
+fn set_analysis_evaluations(board: &UseRef<BoardWithHistory>, analysis: &GameAnalysis) {
+    board.with_mut(|board| {
+        for (position, win_rate_zero) in analysis.win_rates_zero().into_iter().enumerate() {
+            board.set_evaluation(position, win_rate_zero);
+        }
+    });
+}
+
+#[derive(Clone, PartialEq)]
+pub enum AnalysisState {
+    Running(f32),
+    Done(GameAnalysis),
+}
+
+/// The game as the UI sees it, kept up to date with the updates of the engine.
+#[derive(Clone, Copy)]
+pub struct WorkerState<'a> {
+    pub worker: QuoridorWorker<'a>,
+    pub calc_update: &'a UseState<CalculateUpdate>,
+    pub board: &'a UseRef<BoardWithHistory>,
+    pub ai_player: &'a UseState<Option<usize>>,
+    pub book_status: &'a UseState<Option<BookStatus>>,
+    pub analysis: &'a UseState<Option<AnalysisState>>,
+}
+
+impl WorkerState<'_> {
+    /// Analyzes the game so far. A game that was analyzed before comes from the saved game.
+    pub fn analyze(&self) {
+        let start = self.board.read().start.encode();
+        let moves = self.board.read().moves.clone();
+        let saved_game: SavedGame = storage::load(SAVED_GAME_KEY).unwrap_or_default();
+        if let Some(analysis) = saved_game.cached_analysis(&start, &moves, ANALYSIS_VISITS) {
+            set_analysis_evaluations(self.board, analysis);
+            self.analysis
+                .set(Some(AnalysisState::Done(analysis.clone())));
+            return;
+        }
+        self.analysis.set(Some(AnalysisState::Running(0.0)));
+        self.worker.send_command(UserCommand::Analyze {
+            start,
+            moves,
+            visits: ANALYSIS_VISITS,
+        });
+    }
+}
+
+pub fn use_webworker(cx: &ScopeState) -> WorkerState<'_> {
+    let latest_update = use_state(cx, || CalculateUpdate::Progress(0.0));
+    let board = use_ref(cx, || BoardWithHistory::new());
+    let ai_player: &UseState<Option<usize>> = use_state(cx, || None);
+    let book_status: &UseState<Option<BookStatus>> = use_state(cx, || None);
+    let analysis: &UseState<Option<AnalysisState>> = use_state(cx, || None);
+
+    let backend = cx.use_hook(|| {
+        let (updates, mut update_receiver) = unbounded();
+
+        // Whatever the engine runs on, its updates are applied here on the UI side.
+        let latest_update = latest_update.clone();
+        let board = board.clone();
+        let book_status = book_status.clone();
+        let analysis = analysis.clone();
+        cx.spawn(async move {
+            while let Some(calculate_update) = update_receiver.next().await {
+                match calculate_update {
+                    CalculateUpdate::Finish(game_move) => {
+                        //log::info!("AI finish move suggested : {:?}", game_move);
+                        board.with_mut(|board| {
+                            // The user resigned while the AI was still thinking.
+                            if board.result.is_some() {
+                                return;
+                            }
+                            let res = board.game_move(game_move);
+                            info!("Taking AI {:?} MOVE AUTOMATICALLY: {:?}", game_move, res);
+                        });
+                    }
+                    CalculateUpdate::Resign(player) => {
+                        board.with_mut(|board| board.resign(player));
+                        continue;
+                    }
+                    CalculateUpdate::Progress(f) => {
+                        latest_update.set(CalculateUpdate::Progress(f));
+                    }
+                    // Kept apart, so the progress is still shown after it.
+                    CalculateUpdate::Book(status) => {
+                        book_status.set(Some(status));
+                        continue;
+                    }
+                    CalculateUpdate::AnalysisProgress(progress) => {
+                        analysis.set(Some(AnalysisState::Running(progress)));
+                        continue;
+                    }
+                    CalculateUpdate::Evaluation {
+                        position,
+                        win_rate_zero,
+                    } => {
+                        board.with_mut(|board| board.set_evaluation(position, win_rate_zero));
+                        continue;
+                    }
+                    CalculateUpdate::Analysis(game_analysis) => {
+                        set_analysis_evaluations(&board, &game_analysis);
+                        storage::save(
+                            SAVED_GAME_KEY,
+                            &SavedGame {
+                                moves: game_analysis
+                                    .moves
+                                    .iter()
+                                    .map(|analysis| analysis.game_move)
+                                    .collect(),
+                                analysis: Some(game_analysis.clone()),
+                            },
+                        );
+                        analysis.set(Some(AnalysisState::Done(game_analysis)));
+                        continue;
+                    }
+                    _ => {}
+                }
+                latest_update.set(calculate_update);
+            }
+        });
+        new_backend(updates)
+    });
+
+    WorkerState {
+        worker: QuoridorWorker {
+            backend: &**backend,
+        },
+        calc_update: latest_update,
+        board,
+        ai_player,
+        book_status,
+        analysis,
+    }
+}
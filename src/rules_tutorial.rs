@@ -0,0 +1,90 @@
+use dioxus::prelude::*;
+
+use quoridor::*;
+
+use quoridor_frontend::tutorial::TUTORIAL;
+
+use crate::board_fr::{white_at_bottom, BoardGrid};
+use crate::settings::use_locale;
+
+// Teaches the rules one position at a time, on the same board as the game. Only the move the step asks for is played,
+// any other move gets the hint of the step.
+pub fn RulesTutorial(cx: Scope) -> Element {
+    let locale = use_locale(cx);
+    let step_index = use_state(cx, || 0);
+    let board = use_state(cx, || TUTORIAL[0].board());
+    // The message key of the hint for the last move that was not accepted.
+    let hint: &UseState<Option<&'static str>> = use_state(cx, || None);
+    let done = use_state(cx, || false);
+
+    let step = &TUTORIAL[*step_index.get()];
+    let number = *step_index.get() + 1;
+    let count = TUTORIAL.len();
+    let step_text = locale.format(
+        "tutorial_step",
+        &[("number", &locale.count(number as u64)), ("count", &locale.count(count as u64))],
+    );
+    let go_to = move |index: usize| {
+        step_index.set(index);
+        board.set(TUTORIAL[index].board());
+        hint.set(None);
+        done.set(false);
+    };
+
+    cx.render(rsx! {
+        div { class: "page-columns flex flex-col justify-center items-center gap-4",
+        div { class: "flex flex-col items-center",
+            BoardGrid {
+                board: board.get().clone(),
+                // The steps talk about white moving up.
+                flipped: !white_at_bottom(false),
+                interactive: !*done.get(),
+                on_move: move |game_move: Move| {
+                    if step.goal.accepts(board.get(), game_move) {
+                        board.with_mut(|board| board.game_move(game_move));
+                        hint.set(None);
+                        done.set(true);
+                    } else {
+                        hint.set(Some(step.hint));
+                    }
+                },
+            },
+        },
+        div { class: "side-panel flex flex-col space-y-2 w-full p-2",
+            div { class: "text-sm text-gray-600", "{step_text}" },
+            div { class: "text-2xl font-semibold", locale.text(step.title) },
+            div { class: "text-xl", locale.text(step.instructions) },
+            if *done.get() {
+                rsx! { div { class: "bg-green-500 text-white font-bold py-2 px-4 rounded", locale.text(step.success) } }
+            } else if let Some(hint) = hint.get() {
+                rsx! { div { class: "bg-amber-400 font-bold py-2 px-4 rounded", locale.text(hint) } }
+            }
+            div { class: "flex space-x-2",
+                if number > 1 {
+                    rsx! {
+                        button {
+                            class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                            onclick: move |_| go_to(number - 2),
+                            locale.text("back")
+                        }
+                    }
+                }
+                button {
+                    class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                    onclick: move |_| go_to(number - 1),
+                    locale.text("reset")
+                },
+                if *done.get() && number < count {
+                    rsx! {
+                        button {
+                            class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded",
+                            onclick: move |_| go_to(number),
+                            locale.text("next")
+                        }
+                    }
+                }
+            }
+        }
+        }
+    })
+}
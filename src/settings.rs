@@ -0,0 +1,188 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use quoridor_frontend::i18n::Locale;
+
+use crate::storage;
+use crate::theme::ThemeName;
+
+const SETTINGS_KEY: &str = "settings";
+
+/// The preferences of the user. `app` shares them with every page, and they are kept between visits.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    /// Slide the pawns and drop the walls of new moves into place.
+    pub animate_moves: bool,
+    pub theme: ThemeName,
+    /// Draw the board as one SVG instead of a grid of divs, which is lighter to update.
+    pub svg_board: bool,
+    /// The AI varies its opening moves, so practice games don't all go the same way.
+    pub opening_variety: bool,
+    /// The language picked by the user, `None` follows the browser.
+    pub locale: Option<Locale>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            animate_moves: true,
+            theme: ThemeName::default(),
+            svg_board: true,
+            opening_variety: true,
+            locale: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        storage::load(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_KEY, self);
+    }
+}
+
+/// The settings of the page, or the defaults for a component that is shown outside of `app`.
+pub fn use_settings(cx: &ScopeState) -> Settings {
+    use_shared_state::<Settings>(cx)
+        .map(|settings| settings.read().clone())
+        .unwrap_or_default()
+}
+
+/// The first language of the browser there are messages for, natively the language of `LANG`.
+#[cfg(target_arch = "wasm32")]
+pub fn browser_locale() -> Locale {
+    let Some(navigator) = web_sys::window().map(|window| window.navigator()) else {
+        return Locale::default();
+    };
+    let languages: Vec<String> = navigator
+        .languages()
+        .iter()
+        .filter_map(|language| language.as_string())
+        .chain(navigator.language())
+        .collect();
+    Locale::negotiate(languages.iter().map(String::as_str))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn browser_locale() -> Locale {
+    let languages: Vec<String> = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .collect();
+    Locale::negotiate(languages.iter().map(String::as_str))
+}
+
+/// The language to show the page in.
+pub fn use_locale(cx: &ScopeState) -> Locale {
+    let browser_locale = *cx.use_hook(browser_locale);
+    use_settings(cx).locale.unwrap_or(browser_locale)
+}
+
+pub fn SettingsPage(cx: Scope) -> Element {
+    let settings = use_shared_state::<Settings>(cx)?;
+    let animate_moves = settings.read().animate_moves;
+    let current_theme = settings.read().theme;
+    let svg_board = settings.read().svg_board;
+    let opening_variety = settings.read().opening_variety;
+    let current_locale = settings.read().locale;
+    let locale = use_locale(cx);
+    let choice_class = |chosen: bool| {
+        if chosen {
+            "bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        } else {
+            "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded"
+        }
+    };
+
+    cx.render(rsx! {
+        div { class: "flex flex-col items-center space-y-2 p-4",
+            div { class: "text-2xl font-semibold", locale.text("settings") },
+            label { class: "flex items-center space-x-2 text-xl",
+                input {
+                    r#type: "checkbox",
+                    checked: animate_moves,
+                    onclick: move |_| {
+                        let mut settings = settings.write();
+                        settings.animate_moves = !settings.animate_moves;
+                        settings.save();
+                    },
+                },
+                span { locale.text("animate_moves") }
+            },
+            label { class: "flex items-center space-x-2 text-xl",
+                input {
+                    r#type: "checkbox",
+                    checked: svg_board,
+                    onclick: move |_| {
+                        let mut settings = settings.write();
+                        settings.svg_board = !settings.svg_board;
+                        settings.save();
+                    },
+                },
+                span { locale.text("svg_board") }
+            },
+            label { class: "flex items-center space-x-2 text-xl",
+                input {
+                    r#type: "checkbox",
+                    checked: opening_variety,
+                    onclick: move |_| {
+                        let mut settings = settings.write();
+                        settings.opening_variety = !settings.opening_variety;
+                        settings.save();
+                    },
+                },
+                span { locale.text("opening_variety") }
+            },
+            div { class: "text-xl", locale.text("theme") },
+            div { class: "flex space-x-2",
+                ThemeName::ALL.iter().map(|theme| {
+                    let theme = *theme;
+                    let class = choice_class(theme == current_theme);
+                    rsx! {
+                        button {
+                            key: "{theme:?}",
+                            class: class,
+                            onclick: move |_| {
+                                let mut settings = settings.write();
+                                settings.theme = theme;
+                                settings.save();
+                            },
+                            "{theme.label(locale)}"
+                        }
+                    }
+                })
+            }
+            div { class: "text-xl", locale.text("language") },
+            div { class: "flex flex-wrap justify-center gap-2",
+                button {
+                    class: choice_class(current_locale.is_none()),
+                    onclick: move |_| {
+                        let mut settings = settings.write();
+                        settings.locale = None;
+                        settings.save();
+                    },
+                    locale.text("browser_language")
+                },
+                Locale::ALL.iter().map(|choice| {
+                    let choice = *choice;
+                    rsx! {
+                        button {
+                            key: "{choice.code()}",
+                            class: choice_class(current_locale == Some(choice)),
+                            onclick: move |_| {
+                                let mut settings = settings.write();
+                                settings.locale = Some(choice);
+                                settings.save();
+                            },
+                            "{choice.label()}"
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
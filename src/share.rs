@@ -0,0 +1,114 @@
+use quoridor::Board;
+
+use crate::correspondence::CorrespondenceGame;
+use crate::engine::{BoardWithHistory, BookError};
+use crate::text_board::parse_move;
+
+/// A game or a position to send to someone, as the hash or the query of a link: `#game=e2;e8;d3h` has the moves from
+/// the start, `#position=0;10E1;10E9` a `Board::encode()` encoding and `#corr=q1-...` the token of a correspondence
+/// game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SharedLink {
+    /// The moves in Quoridor Strats notation.
+    Game(Vec<String>),
+    Position(String),
+    /// A `CorrespondenceGame::to_token()`, it's only read when the game is opened.
+    Correspondence(String),
+}
+
+impl SharedLink {
+    /// The whole game if it was played from the start, otherwise the position it's in now.
+    pub fn new(history: &BoardWithHistory) -> SharedLink {
+        if history.start.encode() == Board::new().encode() {
+            SharedLink::Game(history.historic_moves.clone())
+        } else {
+            SharedLink::Position(history.board.encode())
+        }
+    }
+
+    /// Finds the game or position in a whole link, or in only its hash or query. The hash wins if there are both.
+    pub fn parse(link: &str) -> Option<SharedLink> {
+        let hash = link.split_once('#').map(|(_, hash)| hash);
+        let query = link
+            .split('#')
+            .next()
+            .and_then(|link| link.split_once('?'))
+            .map(|(_, query)| query);
+        [hash, query]
+            .into_iter()
+            .flatten()
+            .flat_map(|part| part.split('&'))
+            .find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                let value = percent_decode(value);
+                match key {
+                    "game" if value.is_empty() => Some(SharedLink::Game(vec![])),
+                    "game" => Some(SharedLink::Game(
+                        value.split(';').map(str::to_string).collect(),
+                    )),
+                    "position" => Some(SharedLink::Position(value)),
+                    "corr" => Some(SharedLink::Correspondence(value)),
+                    _ => None,
+                }
+            })
+    }
+
+    /// The hash to add to the address of the page, `#` included.
+    pub fn to_hash(&self) -> String {
+        match self {
+            SharedLink::Game(moves) => format!("#game={}", moves.join(";")),
+            SharedLink::Position(encoding) => format!("#position={}", encoding),
+            SharedLink::Correspondence(token) => format!("#corr={}", token),
+        }
+    }
+
+    /// Sets up the game or position. A link can be typed over, so the moves are checked.
+    pub fn board(&self) -> Result<BoardWithHistory, BookError> {
+        match self {
+            SharedLink::Game(moves) => {
+                let mut history = BoardWithHistory::new();
+                for notation in moves {
+                    let game_move = parse_move(&history.board, notation).ok_or_else(|| {
+                        format!(
+                            "{} is not a legal move after {}",
+                            notation,
+                            history.historic_moves()
+                        )
+                    })?;
+                    history.game_move(game_move);
+                }
+                Ok(history)
+            }
+            SharedLink::Position(encoding) => {
+                let board = Board::decode(encoding)
+                    .map_err(|err| format!("{} can't be decoded: {:?}", encoding, err))?;
+                Ok(BoardWithHistory::from_position(board))
+            }
+            SharedLink::Correspondence(token) => Ok(CorrespondenceGame::parse(token)?.board()?),
+        }
+    }
+}
+
+// Browsers and chat apps may escape the `;` of the encodings as `%3B`.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
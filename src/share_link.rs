@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+
+use quoridor_frontend::share::SharedLink;
+
+use crate::settings::use_locale;
+
+const LINK_INPUT_ID: &str = "share-link";
+
+/// The game or position the page was opened with, from the address in the browser and from the first argument
+/// natively.
+#[cfg(target_arch = "wasm32")]
+pub fn startup_link() -> Option<SharedLink> {
+    SharedLink::parse(&web_sys::window()?.location().href().ok()?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn startup_link() -> Option<SharedLink> {
+    SharedLink::parse(&std::env::args().nth(1)?)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn full_link(link: &SharedLink) -> String {
+    let location = web_sys::window().map(|window| window.location());
+    let page = location
+        .and_then(|location| {
+            Some(format!(
+                "{}{}",
+                location.origin().ok()?,
+                location.pathname().ok()?
+            ))
+        })
+        .unwrap_or_default();
+    format!("{}{}", page, link.to_hash())
+}
+
+// The desktop app has no address of its own, it opens the hash when it's given as the first argument.
+#[cfg(not(target_arch = "wasm32"))]
+fn full_link(link: &SharedLink) -> String {
+    link.to_hash()
+}
+
+// The clipboard API of the browser still needs unstable `web_sys` APIs, the old copy command works everywhere.
+#[cfg(target_arch = "wasm32")]
+fn copy_link() {
+    use wasm_bindgen::JsCast;
+
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let input = document
+        .get_element_by_id(LINK_INPUT_ID)
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok());
+    if let Some(input) = input {
+        input.select();
+        let copied = document
+            .dyn_into::<web_sys::HtmlDocument>()
+            .is_ok_and(|document| document.exec_command("copy").unwrap_or(false));
+        if !copied {
+            log::warn!("Could not copy the link, it's selected to copy by hand");
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_link() {
+    log::info!("Copy the link from the field next to the button");
+}
+
+#[derive(Props, PartialEq)]
+pub struct ShareLinkProps {
+    link: SharedLink,
+}
+
+// The link to the game, in a field to copy it by hand as well.
+pub fn ShareLink(cx: Scope<ShareLinkProps>) -> Element {
+    let locale = use_locale(cx);
+    let link = full_link(&cx.props.link);
+
+    cx.render(rsx! {
+        div { class: "flex space-x-2",
+            input {
+                id: LINK_INPUT_ID,
+                class: "border rounded px-2 text-black w-full",
+                readonly: true,
+                value: "{link}",
+            },
+            button {
+                class: "bg-amber-500 hover:bg-amber-700 text-white font-bold py-2 px-4 rounded whitespace-nowrap",
+                onclick: move |_| copy_link(),
+                locale.text("copy_link")
+            }
+        }
+    })
+}
@@ -0,0 +1,62 @@
+// Keeps small things like the last game between visits. In the browser they go to local storage, natively to json
+// files in `~/.quoridor_frontend`.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    web_sys::window()?
+        .local_storage()
+        .ok()??
+        .get_item(key)
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, value: &str) {
+    let storage = web_sys::window().and_then(|window| window.local_storage().ok().flatten());
+    match storage {
+        Some(storage) => {
+            if storage.set_item(key, value).is_err() {
+                log::warn!("Could not store {}", key);
+            }
+        }
+        None => log::warn!("No local storage to store {}", key),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    std::path::Path::new(&home)
+        .join(".quoridor_frontend")
+        .join(format!("{}.json", key))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, value: &str) {
+    let path = path(key);
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, value));
+    if let Err(err) = written {
+        log::warn!("Could not store {}: {}", key, err);
+    }
+}
+
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    serde_json::from_str(&read(key)?).ok()
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(json) => write(key, &json),
+        Err(err) => log::warn!("Could not store {}: {}", key, err),
+    }
+}
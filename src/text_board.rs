@@ -0,0 +1,122 @@
+use quoridor::*;
+
+use crate::grid::*;
+
+pub(crate) fn column_label(col: usize) -> String {
+    Position {
+        row: 0,
+        col: col as i8,
+    }
+    .encode()
+    .chars()
+    .filter(|c| c.is_ascii_alphabetic())
+    .collect()
+}
+
+pub(crate) fn row_label(row: usize) -> String {
+    Position {
+        row: row as i8,
+        col: 0,
+    }
+    .encode()
+    .chars()
+    .filter(|c| c.is_ascii_digit())
+    .collect()
+}
+
+/// Draws the board as text, white is `W` and black is `B`. Uses the same 17x17 grid as the web board, so the odd
+/// rows and columns are the grooves where walls go.
+pub fn render_board(board: &Board, flipped: bool) -> String {
+    let size = DIMENSION * 2 - 1;
+    let rows: Vec<usize> = match flipped {
+        true => (0..size).rev().collect(),
+        false => (0..size).collect(),
+    };
+
+    let mut text = String::from("    ");
+    for col in 0..DIMENSION {
+        text.push_str(&format!(" {} ", column_label(col)));
+        if col + 1 < DIMENSION {
+            text.push(' ');
+        }
+    }
+    text.push('\n');
+
+    for row in rows {
+        if row % 2 == 0 {
+            text.push_str(&format!("{:>3} ", row_label(row / 2)));
+        } else {
+            text.push_str("    ");
+        }
+        for col in 0..size {
+            let square_type = SquareType::at(row, col);
+            let wall = is_part_of_wall(board, square_type, row, col);
+            let cell = match square_type {
+                SquareType::Square => match board.is_pawn(row / 2, col / 2) {
+                    Some(0) => " W ",
+                    Some(_) => " B ",
+                    None => " . ",
+                },
+                SquareType::VerticalBorder => {
+                    if wall {
+                        "|"
+                    } else {
+                        " "
+                    }
+                }
+                SquareType::HorizontalBorder => {
+                    if wall {
+                        "---"
+                    } else {
+                        "   "
+                    }
+                }
+                SquareType::Corner => {
+                    if wall {
+                        "+"
+                    } else {
+                        " "
+                    }
+                }
+            };
+            text.push_str(cell);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// All moves the player to move can make.
+pub fn legal_moves(board: &Board) -> Vec<Move> {
+    let mut moves = vec![];
+    for row in 0..DIMENSION {
+        for col in 0..DIMENSION {
+            if let Some(pawn_move) = board.is_possible_next_pawn_location(row, col) {
+                moves.push(Move::PawnMove(pawn_move.0, pawn_move.1));
+            }
+        }
+    }
+    if board.pawns[board.turn % 2].number_of_walls_left > 0 {
+        for direction in [WallDirection::Horizontal, WallDirection::Vertical] {
+            for row in 0..(DIMENSION - 1) as i8 {
+                for col in 0..(DIMENSION - 1) as i8 {
+                    let location = Position { row, col };
+                    if board.is_wall_allowed(direction, location) {
+                        moves.push(Move::Wall(direction, location));
+                    }
+                }
+            }
+        }
+    }
+    moves
+}
+
+/// Reads a move in Quoridor Strats notation, like `e2` for a pawn move or `d3h` for a wall.
+pub fn parse_move(board: &Board, notation: &str) -> Option<Move> {
+    let notation = notation.trim();
+    legal_moves(board).into_iter().find(|game_move| {
+        game_move
+            .to_quoridor_strat_notation(board)
+            .eq_ignore_ascii_case(notation)
+    })
+}
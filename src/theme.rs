@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use quoridor_frontend::i18n::Locale;
+
+/// The CSS colors the board and the page are drawn with. They are set as inline styles and SVG attributes, so they don't
+/// depend on the classes in the stylesheet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    /// Background and text of the whole page.
+    pub page_background: &'static str,
+    pub page_text: &'static str,
+    /// The frame around the squares.
+    pub board: &'static str,
+    pub square: &'static str,
+    pub groove: &'static str,
+    pub wall: &'static str,
+    /// A groove under the mouse, where a wall would go.
+    pub hover_wall: &'static str,
+    /// Per player, white first.
+    pub pawns: [&'static str; 2],
+    /// Where the pawn of the player to move would go, per player.
+    pub pawn_previews: [&'static str; 2],
+    /// A ring around the pawns and their previews, per player, for pawns that are hard to tell from the squares.
+    pub pawn_outlines: [Option<&'static str>; 2],
+    pub last_move_origin: &'static str,
+    pub last_move_destination: &'static str,
+    pub last_wall: &'static str,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum ThemeName {
+    #[default]
+    Classic,
+    Dark,
+    HighContrast,
+    /// Blue and orange instead of red and green, which look the same with the most common color blindness.
+    ColorBlindSafe,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 4] = [
+        ThemeName::Classic,
+        ThemeName::Dark,
+        ThemeName::HighContrast,
+        ThemeName::ColorBlindSafe,
+    ];
+
+    pub fn label(&self, locale: Locale) -> &'static str {
+        locale.text(match self {
+            ThemeName::Classic => "theme_classic",
+            ThemeName::Dark => "theme_dark",
+            ThemeName::HighContrast => "theme_high_contrast",
+            ThemeName::ColorBlindSafe => "theme_color_blind_safe",
+        })
+    }
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeName::Classic => Theme {
+                page_background: "#ffffff",
+                page_text: "#000000",
+                board: "#f3f4f6",
+                square: "#d97706",
+                groove: "#fbbf24",
+                wall: "#92400e",
+                hover_wall: "#b45309",
+                pawns: ["#f1f5f9", "#0f172a"],
+                pawn_previews: ["#e2e8f0", "#1e293b"],
+                pawn_outlines: [None, None],
+                last_move_origin: "#fcd34d",
+                last_move_destination: "#f59e0b",
+                last_wall: "#b91c1c",
+            },
+            ThemeName::Dark => Theme {
+                page_background: "#111827",
+                page_text: "#f3f4f6",
+                board: "#1f2937",
+                square: "#44403c",
+                groove: "#57534e",
+                wall: "#f59e0b",
+                hover_wall: "#b45309",
+                pawns: ["#f1f5f9", "#0369a1"],
+                pawn_previews: ["#94a3b8", "#0c4a6e"],
+                pawn_outlines: [None, None],
+                last_move_origin: "#78716c",
+                last_move_destination: "#a8a29e",
+                last_wall: "#ef4444",
+            },
+            ThemeName::HighContrast => Theme {
+                page_background: "#ffffff",
+                page_text: "#000000",
+                board: "#000000",
+                square: "#ffffff",
+                groove: "#9ca3af",
+                wall: "#000000",
+                hover_wall: "#1d4ed8",
+                pawns: ["#fde047", "#000000"],
+                pawn_previews: ["#fef9c3", "#4b5563"],
+                pawn_outlines: [Some("#000000"), Some("#ffffff")],
+                last_move_origin: "#d1d5db",
+                last_move_destination: "#fef9c3",
+                last_wall: "#1d4ed8",
+            },
+            ThemeName::ColorBlindSafe => Theme {
+                page_background: "#ffffff",
+                page_text: "#000000",
+                board: "#f3f4f6",
+                square: "#bae6fd",
+                groove: "#38bdf8",
+                wall: "#1e3a8a",
+                hover_wall: "#2563eb",
+                pawns: ["#ffffff", "#000000"],
+                pawn_previews: ["#e5e7eb", "#374151"],
+                pawn_outlines: [Some("#000000"), None],
+                last_move_origin: "#fed7aa",
+                last_move_destination: "#fb923c",
+                last_wall: "#ea580c",
+            },
+        }
+    }
+}
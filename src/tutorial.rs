@@ -0,0 +1,103 @@
+use quoridor::{Board, Move};
+
+/// What the user has to do to finish a step of the tutorial.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TutorialGoal {
+    AnyPawnMove,
+    AnyWall,
+    /// One of these moves, in Quoridor Strats notation.
+    Moves(&'static [&'static str]),
+}
+
+impl TutorialGoal {
+    pub fn accepts(&self, board: &Board, game_move: Move) -> bool {
+        match self {
+            TutorialGoal::AnyPawnMove => matches!(game_move, Move::PawnMove(..)),
+            TutorialGoal::AnyWall => matches!(game_move, Move::Wall(..)),
+            TutorialGoal::Moves(moves) => {
+                let notation = game_move.to_quoridor_strat_notation(board);
+                moves
+                    .iter()
+                    .any(|expected| expected.eq_ignore_ascii_case(&notation))
+            }
+        }
+    }
+}
+
+/// One position of the tutorial, white is always to move. The texts are the keys of their messages in the catalogs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TutorialStep {
+    pub title: &'static str,
+    /// A `Board::encode()` encoding.
+    pub encoding: &'static str,
+    pub instructions: &'static str,
+    pub goal: TutorialGoal,
+    /// Shown when the user makes a move the goal doesn't accept, the move is not played.
+    pub hint: &'static str,
+    pub success: &'static str,
+}
+
+impl TutorialStep {
+    pub fn board(&self) -> Board {
+        Board::decode(self.encoding).unwrap()
+    }
+}
+
+pub const TUTORIAL: &[TutorialStep] = &[
+    TutorialStep {
+        title: "tutorial_moving_title",
+        encoding: "0;10E1;10E9",
+        instructions: "tutorial_moving_instructions",
+        goal: TutorialGoal::AnyPawnMove,
+        hint: "tutorial_moving_hint",
+        success: "tutorial_moving_success",
+    },
+    TutorialStep {
+        title: "tutorial_goal_rows_title",
+        encoding: "14;10E8;10A5",
+        instructions: "tutorial_goal_rows_instructions",
+        goal: TutorialGoal::Moves(&["e9"]),
+        hint: "tutorial_goal_rows_hint",
+        success: "tutorial_goal_rows_success",
+    },
+    TutorialStep {
+        title: "tutorial_walls_block_title",
+        encoding: "2;10E1;9E9;D1h",
+        instructions: "tutorial_walls_block_instructions",
+        goal: TutorialGoal::AnyPawnMove,
+        hint: "tutorial_walls_block_hint",
+        success: "tutorial_walls_block_success",
+    },
+    TutorialStep {
+        title: "tutorial_placing_walls_title",
+        encoding: "0;10E1;10E9",
+        instructions: "tutorial_placing_walls_instructions",
+        goal: TutorialGoal::Moves(&["d8h", "e8h"]),
+        hint: "tutorial_placing_walls_hint",
+        success: "tutorial_placing_walls_success",
+    },
+    TutorialStep {
+        title: "tutorial_jumping_title",
+        encoding: "6;10E4;10E5",
+        instructions: "tutorial_jumping_instructions",
+        goal: TutorialGoal::Moves(&["e6"]),
+        hint: "tutorial_jumping_hint",
+        success: "tutorial_jumping_success",
+    },
+    TutorialStep {
+        title: "tutorial_diagonal_jumps_title",
+        encoding: "6;10E4;9E5;D5h",
+        instructions: "tutorial_diagonal_jumps_instructions",
+        goal: TutorialGoal::Moves(&["d5", "f5"]),
+        hint: "tutorial_diagonal_jumps_hint",
+        success: "tutorial_diagonal_jumps_success",
+    },
+    TutorialStep {
+        title: "tutorial_never_block_title",
+        encoding: "2;10E2;9A9;A8v",
+        instructions: "tutorial_never_block_instructions",
+        goal: TutorialGoal::AnyWall,
+        hint: "tutorial_never_block_hint",
+        success: "tutorial_never_block_success",
+    },
+];
@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+
+const WIDTH: f32 = 400.0;
+const HEIGHT: f32 = 100.0;
+
+#[derive(Props)]
+pub struct WinRateGraphProps<'a> {
+    // The chance white wins after every number of moves, `None` where it's unknown.
+    evaluations: Vec<Option<f32>>,
+    #[props(!optional)]
+    selected: Option<usize>,
+    on_select: EventHandler<'a, usize>,
+}
+
+// The chance white wins over the course of the game, white winning at the top. Clicking a point shows that position.
+pub fn WinRateGraph<'a>(cx: Scope<'a, WinRateGraphProps<'a>>) -> Element<'a> {
+    let evaluations = &cx.props.evaluations;
+    if evaluations.iter().all(Option::is_none) {
+        return None;
+    }
+    let step = WIDTH / (evaluations.len().max(2) - 1) as f32;
+    let points: Vec<(usize, f32, f32)> = evaluations
+        .iter()
+        .enumerate()
+        .filter_map(|(position, win_rate)| {
+            win_rate.map(|win_rate| (position, position as f32 * step, (1.0 - win_rate) * HEIGHT))
+        })
+        .collect();
+    let line = points
+        .iter()
+        .map(|(_, x, y)| format!("{:.1},{:.1}", x, y))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let on_select = &cx.props.on_select;
+    let middle = HEIGHT / 2.0;
+
+    cx.render(rsx! {
+        svg {
+            class: "w-full bg-slate-100 rounded",
+            view_box: "-4 -4 {WIDTH + 8.0} {HEIGHT + 8.0}",
+            line { x1: "0", y1: "{middle}", x2: "{WIDTH}", y2: "{middle}", stroke: "#9ca3af", stroke_dasharray: "4" },
+            polyline { points: "{line}", fill: "none", stroke: "#b45309", stroke_width: "2" },
+            points.iter().map(|(position, x, y)| {
+                let position = *position;
+                let radius = if cx.props.selected == Some(position) { 5 } else { 3 };
+                rsx! {
+                    circle {
+                        key: "{position}",
+                        cx: "{x}",
+                        cy: "{y}",
+                        r: "{radius}",
+                        fill: "#92400e",
+                        style: "cursor: pointer",
+                        onclick: move |_| on_select.call(position),
+                    }
+                }
+            })
+        }
+    })
+}